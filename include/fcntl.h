@@ -0,0 +1,11 @@
+// <fcntl.h> for c4: opening files through the virtual machine
+#ifndef _C4_FCNTL_H
+#define _C4_FCNTL_H
+
+#define O_RDONLY 0
+#define O_WRONLY 1
+#define O_RDWR 2
+
+int open(char *path, int flags);
+
+#endif
@@ -0,0 +1,10 @@
+// <stdio.h> for c4: formatted output built into the virtual machine
+#ifndef _C4_STDIO_H
+#define _C4_STDIO_H
+
+#define NULL ((void *)0)
+#define EOF (-1)
+
+int printf(char *fmt, ...);
+
+#endif
@@ -0,0 +1,13 @@
+// <stdlib.h> for c4: memory allocation and exit built into the virtual machine
+#ifndef _C4_STDLIB_H
+#define _C4_STDLIB_H
+
+#define NULL ((void *)0)
+#define EXIT_SUCCESS 0
+#define EXIT_FAILURE 1
+
+void *malloc(unsigned long size);
+void free(void *ptr);
+void exit(int status);
+
+#endif
@@ -0,0 +1,10 @@
+// <string.h> for c4: memory functions built into the virtual machine
+#ifndef _C4_STRING_H
+#define _C4_STRING_H
+
+#define NULL ((void *)0)
+
+void *memset(void *s, int c, unsigned long n);
+int memcmp(void *s1, void *s2, unsigned long n);
+
+#endif
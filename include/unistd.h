@@ -0,0 +1,10 @@
+// <unistd.h> for c4: reading and closing files through the virtual machine
+#ifndef _C4_UNISTD_H
+#define _C4_UNISTD_H
+
+#define NULL ((void *)0)
+
+long read(int fd, void *buf, unsigned long n);
+int close(int fd);
+
+#endif
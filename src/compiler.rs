@@ -0,0 +1,3471 @@
+// Lexer, parser and code generator for the C4 subset of C
+
+use std::path::PathBuf;
+
+use crate::error::{CompileError, DeclKind, ErrorKind, LvalueContext, Span};
+use crate::opcode::*;
+use crate::preprocess::{LineOrigin, Preprocessor, SourceFile};
+use crate::token::*;
+use crate::types::{Signature, Ty, TypeKind, Types, CHAR, DOUBLE, FLOAT, INT, LONG, SHORT, UCHAR, UINT, ULONG, USHORT, VOID, WORD_SIZE};
+
+// Default size in bytes of each memory pool (code, data, source)
+pub const POOL_SIZE: usize = 256 * 1024;
+
+// Default number of errors reported before compilation stops
+pub const MAX_ERRORS: usize = 20;
+
+// Keywords and system calls, tokenized in this order when a compiler is created
+const KEYWORDS: &str = "char else enum if int return sizeof while for do break continue switch case default goto struct union typedef short long unsigned signed float double void open read close printf malloc free memset memcmp exit main";
+
+// One entry of the symbol table
+#[derive(Clone, Debug, Default)]
+pub struct Symbol {
+    pub tk: Token, // Token type
+    pub hash: i64, // Hash value for identifier
+    pub name: String, // Name of identifier
+    pub class: i64, // Class (e.g., global, local)
+    pub ty: Ty, // Type (e.g., int, char)
+    pub val: i64, // Value or address
+    pub span: Option<Span>, // Where the current meaning was defined (None for built-ins)
+    pub params: Option<Vec<Ty>>, // Parameter types of a function, once a declaration other than `()` gave them
+    pub variadic: bool, // Function takes more arguments after its parameters ('...')
+}
+
+// Meaning a symbol had before a declaration in a block shadowed it
+#[derive(Debug)]
+struct Shadowed {
+    id: usize, // Symbol declared in the block
+    class: i64, // Saved class
+    ty: Ty, // Saved type
+    val: i64, // Saved value
+    span: Option<Span>, // Saved definition location
+}
+
+// What closing a block scope has to restore
+#[derive(Debug)]
+struct Scope {
+    start: usize, // Length of the shadowed stack when the block was opened
+    outer: usize, // Start of the enclosing block's declarations in the shadowed stack
+    slot: i64, // Stack slots in use when the block was opened
+    tags: usize, // Number of struct and union tags in scope when the block was opened
+    outer_tags: usize, // Start of the enclosing block's tags
+}
+
+// Jumps out of a loop or switch body waiting for their targets to be known
+#[derive(Debug, Default)]
+struct Loop {
+    breaks: Vec<usize>, // Operands of JMPs to the exit
+    continues: Vec<usize>, // Operands of JMPs to the next iteration
+    switch: bool, // A switch body: takes breaks but not continues
+}
+
+// Labels seen so far in the body of a switch
+#[derive(Debug, Default)]
+struct Switch {
+    ty: Ty, // Promoted type of the controlling expression, which case values convert to
+    cases: Vec<(i64, usize, Span)>, // Value, code address and location of each case
+    default: Option<usize>, // Code address of the default label
+}
+
+// A label of the function being compiled
+#[derive(Debug)]
+struct Label {
+    id: usize, // Symbol of the label's name (labels have a namespace of their own)
+    addr: Option<usize>, // Code address, once the label is defined
+    span: Option<Span>, // Where the label is defined
+    used: Option<Span>, // First goto referring to the label
+    pending: Vec<usize>, // Operands of JMPs waiting for the address
+}
+
+// A parameter in a function declarator
+#[derive(Debug)]
+struct Param {
+    ty: Ty, // Type, after arrays are adjusted to pointers
+    name: Option<usize>, // Symbol of the name, which a prototype may leave out
+    span: Span, // Location of the name, or of the type if there is none
+}
+
+// A declarator parsed with the parameter names of the function it declares
+#[derive(Debug)]
+struct Declarator {
+    ty: Ty, // Declared type
+    name: Option<usize>, // Symbol of the name, which type names leave out
+    span: Span, // Location of the name, or of the declarator if there is none
+    params: Option<Vec<Param>>, // Parameter list directly after the name, if there is one
+}
+
+// One step from a declarator's base type towards the type it declares
+enum Derive {
+    Ptr, // Pointer to the type so far
+    Array(i64, Span), // Array of it (0 elements while the size is unknown), and where its '[' is
+    Func(Option<Vec<Ty>>, bool, Span), // Function returning it, its parameter types (None for `()`), whether it is variadic, and where its '(' is
+}
+
+// Where the values of an initializer go
+enum InitTarget {
+    Data(Vec<(usize, Ty, i64)>, Vec<(usize, usize, Span)>), // Constants for a global: byte offset, type and value; and addresses of functions not defined yet: byte offset, symbol and location
+    Frame(Vec<usize>), // Stores for a local: operands of the LEAs of the local's address
+}
+
+// Smallest number of cases worth a jump table
+const JTAB_MIN_CASES: usize = 4;
+
+// A compiled program, ready to be loaded into a `Vm`
+pub struct Program {
+    pub code: Box<[i64]>, // Code segment; jump targets are indices into it
+    pub data: Box<[i64]>, // Data segment; globals and strings are addressed by host pointer
+    pub main: usize, // Code index of main()
+    pub exit: usize, // Code index of the PSH/EXIT stub main() returns into
+}
+
+// Compiler state: lexer position, symbol table and code/data buffers
+pub struct Compiler {
+    pub listing: bool, // Print source lines and generated code while compiling (-s)
+    pub max_errors: usize, // Stop compiling after this many errors
+    pp: Preprocessor, // Preprocessor run in front of the lexer
+    origins: Vec<LineOrigin>, // File and line each line of preprocessed source came from
+    src: Vec<u8>, // Preprocessed source code, NUL-terminated
+    p: usize, // Current position in source code
+    code: Box<[i64]>, // Code segment for generated code
+    e: usize, // Last emitted code position
+    load: Option<usize>, // Position of the load emitted for an lvalue, while it is the last word
+    le: usize, // Last listed code position
+    data: Box<[i64]>, // Data segment for globals and strings
+    dp: usize, // Next free byte in the data segment
+    sym: Vec<Symbol>, // Symbol table
+    id: usize, // Current identifier in symbol table
+    tk: Token, // Current token
+    ival: i64, // Value of current numeric literal
+    num_ty: Ty, // Type of current numeric literal
+    ty: Ty, // Type of current expression
+    ret: Ty, // Return type of the current function
+    types: Types, // Every type seen so far
+    tags: Vec<(usize, Ty, Span)>, // Struct and union tags in scope: symbol, type and where declared
+    tag_scope: usize, // Start of the current block's tags
+    loc: i64, // Local variable offset
+    slot: i64, // Stack slots in use by the locals in scope
+    frame: i64, // Most stack slots in use at once in the current function
+    depth: usize, // Nesting depth of block scopes (0 at file scope)
+    scope: usize, // Start of the current block's declarations in the shadowed stack
+    shadowed: Vec<Shadowed>, // Outer meanings of the symbols declared in open blocks
+    line: usize, // Current line number
+    line_start: usize, // Position of the first character of the current line
+    tk_start: usize, // Position of the first character of the current token
+    idmain: usize, // Symbol of the main function
+    errors: Vec<CompileError>, // Errors reported so far
+    loops: Vec<Loop>, // Enclosing loops and switches, innermost last
+    switches: Vec<Switch>, // Enclosing switches, innermost last
+    labels: Vec<Label>, // Labels of the current function
+    calls: Vec<(usize, usize, Span)>, // Calls to functions not defined yet: symbol, JSR operand and location
+    data_calls: Vec<(usize, usize, Span)>, // Addresses of functions not defined yet in the data segment: symbol, byte offset and location
+    warnings: Vec<CompileError>, // Warnings reported so far
+    full: bool, // The program outgrew a segment; the rest of the source is skipped
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Compiler::new()
+    }
+}
+
+impl Compiler {
+    // Creates a compiler with the keywords and system calls already in its symbol table
+    pub fn new() -> Compiler {
+        let mut c = Compiler {
+            listing: false,
+            max_errors: MAX_ERRORS,
+            pp: Preprocessor::new(),
+            origins: Vec::new(),
+            src: Vec::new(),
+            p: 0,
+            code: vec![0; POOL_SIZE / WORD_SIZE as usize].into_boxed_slice(),
+            e: 0,
+            load: None,
+            le: 0,
+            data: vec![0; POOL_SIZE / WORD_SIZE as usize].into_boxed_slice(),
+            dp: 0,
+            sym: Vec::new(),
+            id: 0,
+            tk: 0,
+            ival: 0,
+            num_ty: INT,
+            ty: INT,
+            ret: INT,
+            types: Types::new(),
+            tags: Vec::new(),
+            tag_scope: 0,
+            loc: 0,
+            slot: 0,
+            frame: 0,
+            depth: 0,
+            scope: 0,
+            shadowed: Vec::new(),
+            line: 0,
+            line_start: 0,
+            tk_start: 0,
+            idmain: 0,
+            errors: Vec::new(),
+            loops: Vec::new(),
+            switches: Vec::new(),
+            labels: Vec::new(),
+            calls: Vec::new(),
+            data_calls: Vec::new(),
+            warnings: Vec::new(),
+            full: false,
+        };
+
+        // Initialize keyword and system call symbols
+        c.set_source(KEYWORDS);
+        for i in Char..Assign {
+            c.next(); // Tokenize keyword
+            c.sym[c.id].tk = i; // Assign token type
+        }
+        for i in OPEN..=EXIT {
+            c.next(); // Tokenize system call
+            c.sym[c.id].class = Sys; // Mark as system call
+            c.sym[c.id].ty = INT; // Set return type
+            c.sym[c.id].val = i; // Set syscall number
+        }
+        c.next(); // Tokenize 'main'
+        c.idmain = c.id; // Save main function symbol
+
+        // Emit the stub main() returns into: push its result and exit
+        c.emit(PSH);
+        c.emit(EXIT);
+        c.le = c.e;
+        c
+    }
+
+    // Replaces the source being tokenized
+    fn set_source(&mut self, source: &str) {
+        self.src = Vec::with_capacity(source.len() + 1);
+        self.src.extend_from_slice(source.as_bytes());
+        self.src.push(0); // Null-terminate source
+        self.p = 0;
+        self.line_start = 0;
+        self.tk_start = 0;
+    }
+
+    // Location of the current token
+    fn span(&self) -> Span {
+        Span {
+            start: self.tk_start,
+            end: self.p.max(self.tk_start),
+            line: self.line,
+            column: self.tk_start - self.line_start + 1,
+            file: 0,
+        }
+    }
+
+    // Builds an error of the given kind at the current token
+    fn error(&self, kind: ErrorKind) -> CompileError {
+        CompileError::new(kind, self.span())
+    }
+
+    // Error for symbol `id` being defined again at `at`
+    fn redefinition(&self, kind: DeclKind, id: usize, at: Span) -> CompileError {
+        let err = CompileError::new(ErrorKind::DuplicateDefinition(kind), at);
+        match self.sym[id].span {
+            Some(prev) => err.with_note("previous definition was here", prev),
+            None => err,
+        }
+    }
+
+    // Appends one word to the code segment
+    fn emit(&mut self, v: i64) {
+        if self.e + 1 >= self.code.len() {
+            self.out_of_room(ErrorKind::ProgramTooLarge);
+            return;
+        }
+        self.e += 1;
+        self.code[self.e] = v;
+        self.load = None;
+    }
+
+    // Reports that a segment of the program is full and stops compiling: errors after
+    // this one would only come from the code that could not be stored
+    fn out_of_room(&mut self, kind: ErrorKind) {
+        if !self.full {
+            let err = self.error(kind);
+            self.report(err);
+            self.full = true;
+            self.p = self.src.len() - 1;
+            self.tk = 0;
+        }
+    }
+
+    // Host address of a byte offset in the data segment
+    fn data_addr(&self, off: usize) -> i64 {
+        self.data.as_ptr() as i64 + off as i64
+    }
+
+    // Stores one byte at the next free position in the data segment
+    fn push_data_byte(&mut self, b: u8) {
+        if self.dp >= POOL_SIZE {
+            self.out_of_room(ErrorKind::DataTooLarge);
+            return;
+        }
+        self.set_data_byte(self.dp, b);
+        self.dp += 1;
+    }
+
+    // Allocates `bytes` at the end of the data segment, returning false if they do not fit
+    fn alloc_data(&mut self, bytes: usize) -> bool {
+        if bytes > POOL_SIZE - self.dp {
+            self.out_of_room(ErrorKind::DataTooLarge);
+            return false;
+        }
+        self.dp += bytes;
+        true
+    }
+
+    // Stores one byte at a byte offset in the data segment
+    fn set_data_byte(&mut self, off: usize, b: u8) {
+        let word = off / WORD_SIZE as usize;
+        let mut bytes = self.data[word].to_ne_bytes();
+        bytes[off % WORD_SIZE as usize] = b;
+        self.data[word] = i64::from_ne_bytes(bytes);
+    }
+
+    // Prints the current source line, as written in the file being compiled, and the
+    // code generated for it (-s); lines of included files are not shown
+    fn list_line(&mut self) {
+        if let Some(o) = self.origins.get(self.line - 1).filter(|o| o.file == 0) {
+            let text = self.pp.files()[0].text[o.offset..].split('\n').next().unwrap_or("");
+            println!("{}: {}", o.line, text.trim_end_matches('\r'));
+        }
+        // Print generated opcodes from last emitted to current
+        while self.le < self.e {
+            self.le += 1;
+            let op = self.code[self.le];
+            print!("{:>8}", op_name(op));
+            // If opcode requires an operand (e.g., LEA, IMM), print it
+            if has_operand(op) {
+                self.le += 1;
+                println!(" {}", self.code[self.le]);
+            } else {
+                println!();
+            }
+        }
+    }
+
+    // Accounts for a newline just consumed by the lexer
+    fn newline(&mut self) {
+        // If source output is enabled, print the current line and generated code
+        if self.listing {
+            self.list_line();
+        }
+        self.line += 1; // Increment line number
+        self.line_start = self.p;
+    }
+
+    // Decodes the escape sequence following a backslash in a literal; returns None for a
+    // backslash-newline, which continues the literal on the next line
+    fn escape(&mut self) -> Option<u8> {
+        let at = self.p - 1; // Position of the backslash
+        let c = self.src[self.p];
+        if c == 0 {
+            return Some(b'\\');
+        }
+        self.p += 1;
+        let v = match c {
+            b'a' => 7, // Alert
+            b'b' => 8, // Backspace
+            b'f' => 12, // Form feed
+            b'n' => b'\n', // Newline
+            b'r' => b'\r', // Carriage return
+            b't' => b'\t', // Horizontal tab
+            b'v' => 11, // Vertical tab
+            b'\\' | b'\'' | b'"' | b'?' => c,
+            // Octal escape: up to three octal digits
+            b'0'..=b'7' => {
+                let mut v = (c - b'0') as u32;
+                for _ in 0..2 {
+                    if !(b'0'..=b'7').contains(&self.src[self.p]) {
+                        break;
+                    }
+                    v = v * 8 + (self.src[self.p] - b'0') as u32;
+                    self.p += 1;
+                }
+                v as u8
+            }
+            // Hexadecimal escape: any number of hex digits
+            b'x' if self.src[self.p].is_ascii_hexdigit() => {
+                let mut v: u32 = 0;
+                while self.src[self.p].is_ascii_hexdigit() {
+                    v = v.wrapping_mul(16).wrapping_add((self.src[self.p] as char).to_digit(16).unwrap_or(0));
+                    self.p += 1;
+                }
+                v as u8
+            }
+            b'\n' => {
+                self.newline();
+                return None;
+            }
+            _ => {
+                let text = String::from_utf8_lossy(&self.src[at..self.p]).into_owned();
+                let span = Span { start: at, end: self.p, line: self.line, column: at - self.line_start + 1, file: 0 };
+                self.report(CompileError::new(ErrorKind::BadEscape(text), span));
+                c
+            }
+        };
+        Some(v)
+    }
+
+    // Tokenizes the next token of the source code
+    fn next(&mut self) {
+        // Loop through the source code until no more tokens or end of input
+        loop {
+            // Get the current character as a token
+            self.tk_start = self.p;
+            self.tk = self.src[self.p] as i64;
+            // Exit loop if end of input (null character)
+            if self.tk == 0 {
+                return;
+            }
+            // Move to the next character in the source code
+            self.p += 1;
+            let c = self.tk as u8;
+
+            // Handle newline character
+            if c == b'\n' {
+                self.newline();
+            }
+            // Handle identifiers (letters, underscores, followed by alphanumerics or underscores)
+            else if c.is_ascii_alphabetic() || c == b'_' {
+                let pp = self.p - 1; // Mark start of identifier
+                // Compute a hash while scanning identifier characters
+                while self.src[self.p].is_ascii_alphanumeric() || self.src[self.p] == b'_' {
+                    self.tk = self.tk.wrapping_mul(147).wrapping_add(self.src[self.p] as i64);
+                    self.p += 1;
+                }
+                // Finalize hash with length of identifier
+                self.tk = (self.tk << 6).wrapping_add((self.p - pp) as i64);
+                let name = &self.src[pp..self.p];
+                // Search symbol table for existing identifier
+                if let Some(i) = self.sym.iter().position(|s| s.hash == self.tk && s.name.as_bytes() == name) {
+                    self.id = i;
+                    self.tk = self.sym[i].tk; // Found, use existing token
+                    return;
+                }
+                // New identifier, add to symbol table
+                self.sym.push(Symbol {
+                    tk: Id,
+                    hash: self.tk,
+                    name: String::from_utf8_lossy(name).into_owned(),
+                    ..Symbol::default()
+                });
+                self.id = self.sym.len() - 1;
+                self.tk = Id;
+                return;
+            }
+            // Handle numeric literals (decimal, hexadecimal, octal)
+            else if c.is_ascii_digit() {
+                if self.float_literal(self.p - 1) {
+                    return;
+                }
+                self.ival = self.tk - b'0' as i64; // Start with first digit
+                // Decimal number (non-zero leading digit)
+                if self.ival != 0 {
+                    while self.src[self.p].is_ascii_digit() {
+                        self.ival = self.ival.wrapping_mul(10).wrapping_add((self.src[self.p] - b'0') as i64);
+                        self.p += 1;
+                    }
+                }
+                // Hexadecimal number (starts with 0x or 0X)
+                else if self.src[self.p] == b'x' || self.src[self.p] == b'X' {
+                    loop {
+                        self.p += 1;
+                        let h = self.src[self.p];
+                        if !h.is_ascii_hexdigit() {
+                            break;
+                        }
+                        self.ival = self
+                            .ival
+                            .wrapping_mul(16)
+                            .wrapping_add((h & 15) as i64 + if h >= b'A' { 9 } else { 0 });
+                    }
+                }
+                // Octal number (starts with 0)
+                else {
+                    while (b'0'..=b'7').contains(&self.src[self.p]) {
+                        self.ival = self.ival.wrapping_mul(8).wrapping_add((self.src[self.p] - b'0') as i64);
+                        self.p += 1;
+                    }
+                }
+                self.num_ty = self.literal_type(c != b'0');
+                self.tk = Num; // Set token as number
+                return;
+            }
+            // Handle division operator or comments
+            else if c == b'/' {
+                // Single-line comment (//)
+                if self.src[self.p] == b'/' {
+                    self.p += 1;
+                    while self.src[self.p] != 0 && self.src[self.p] != b'\n' {
+                        self.p += 1;
+                    }
+                }
+                // Block comment (/* ... */), which may span lines
+                else if self.src[self.p] == b'*' {
+                    let at = Span { end: self.p + 1, ..self.span() };
+                    self.p += 1;
+                    while self.src[self.p] != 0 && !(self.src[self.p] == b'*' && self.src[self.p + 1] == b'/') {
+                        self.p += 1;
+                        if self.src[self.p - 1] == b'\n' {
+                            self.newline();
+                        }
+                    }
+                    if self.src[self.p] == 0 {
+                        self.report(CompileError::new(ErrorKind::UnterminatedComment, at));
+                    } else {
+                        self.p += 2; // Skip closing */
+                    }
+                } else if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = DivAssign; // Divide and assign (/=)
+                    return;
+                } else {
+                    self.tk = Div; // Division operator
+                    return;
+                }
+            }
+            // Handle character or string literals
+            else if c == b'\'' || c == b'"' {
+                let pp = self.dp; // Point to data segment for strings
+                // Process characters until closing quote
+                while self.src[self.p] != 0 && self.src[self.p] != c {
+                    let mut b = self.src[self.p];
+                    self.p += 1;
+                    // Handle escape sequences (e.g., \n, \101, \x41)
+                    if b == b'\\' {
+                        match self.escape() {
+                            Some(v) => b = v,
+                            None => continue, // Line continuation
+                        }
+                    }
+                    self.ival = b as i8 as i64; // Characters are signed
+                    // Store characters for string literals
+                    if c == b'"' {
+                        self.push_data_byte(b);
+                    }
+                }
+                if self.src[self.p] != 0 {
+                    self.p += 1; // Skip closing quote
+                }
+                // For strings, set ival to data segment address
+                if c == b'"' {
+                    self.ival = self.data_addr(pp);
+                } else {
+                    self.tk = Num; // Character treated as number
+                    self.num_ty = INT;
+                }
+                return;
+            }
+            // Handle assignment or equality operators
+            else if c == b'=' {
+                if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = Eq; // Equality (==)
+                } else {
+                    self.tk = Assign; // Assignment (=)
+                }
+                return;
+            }
+            // Handle addition or increment operators
+            else if c == b'+' {
+                if self.src[self.p] == b'+' {
+                    self.p += 1;
+                    self.tk = Inc; // Increment (++)
+                } else if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = AddAssign; // Add and assign (+=)
+                } else {
+                    self.tk = Add; // Addition (+)
+                }
+                return;
+            }
+            // Handle subtraction, decrement or member access operators
+            else if c == b'-' {
+                if self.src[self.p] == b'-' {
+                    self.p += 1;
+                    self.tk = Dec; // Decrement (--)
+                } else if self.src[self.p] == b'>' {
+                    self.p += 1;
+                    self.tk = Arrow; // Member access through a pointer (->)
+                } else if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = SubAssign; // Subtract and assign (-=)
+                } else {
+                    self.tk = Sub; // Subtraction (-)
+                }
+                return;
+            }
+            // Handle not equal operator
+            else if c == b'!' {
+                if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = Ne; // Not equal (!=)
+                }
+                return;
+            }
+            // Handle less than, less than or equal, or left shift operators
+            else if c == b'<' {
+                if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = Le; // Less or equal (<=)
+                } else if self.src[self.p] == b'<' {
+                    self.p += 1;
+                    self.tk = Shl; // Left shift (<<)
+                    if self.src[self.p] == b'=' {
+                        self.p += 1;
+                        self.tk = ShlAssign; // Shift left and assign (<<=)
+                    }
+                } else {
+                    self.tk = Lt; // Less than (<)
+                }
+                return;
+            }
+            // Handle greater than, greater than or equal, or right shift operators
+            else if c == b'>' {
+                if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = Ge; // Greater or equal (>=)
+                } else if self.src[self.p] == b'>' {
+                    self.p += 1;
+                    self.tk = Shr; // Right shift (>>)
+                    if self.src[self.p] == b'=' {
+                        self.p += 1;
+                        self.tk = ShrAssign; // Shift right and assign (>>=)
+                    }
+                } else {
+                    self.tk = Gt; // Greater than (>)
+                }
+                return;
+            }
+            // Handle bitwise or logical OR operators
+            else if c == b'|' {
+                if self.src[self.p] == b'|' {
+                    self.p += 1;
+                    self.tk = Lor; // Logical OR (||)
+                } else if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = OrAssign; // Bitwise OR and assign (|=)
+                } else {
+                    self.tk = Or; // Bitwise OR (|)
+                }
+                return;
+            }
+            // Handle bitwise or logical AND operators
+            else if c == b'&' {
+                if self.src[self.p] == b'&' {
+                    self.p += 1;
+                    self.tk = Lan; // Logical AND (&&)
+                } else if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = AndAssign; // Bitwise AND and assign (&=)
+                } else {
+                    self.tk = And; // Bitwise AND (&)
+                }
+                return;
+            }
+            // Handle bitwise XOR operator
+            else if c == b'^' {
+                if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = XorAssign; // Bitwise XOR and assign (^=)
+                } else {
+                    self.tk = Xor; // Bitwise XOR (^)
+                }
+                return;
+            }
+            // Handle modulo operator
+            else if c == b'%' {
+                if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = ModAssign; // Modulo and assign (%=)
+                } else {
+                    self.tk = Mod; // Modulo (%)
+                }
+                return;
+            }
+            // Handle multiplication operator
+            else if c == b'*' {
+                if self.src[self.p] == b'=' {
+                    self.p += 1;
+                    self.tk = MulAssign; // Multiply and assign (*=)
+                } else {
+                    self.tk = Mul; // Multiplication (*)
+                }
+                return;
+            }
+            // Handle array subscript operator
+            else if c == b'[' {
+                self.tk = Brak;
+                return;
+            }
+            // Handle member access operator
+            else if c == b'.' {
+                // Unless it starts a floating-point literal such as .5
+                if self.src[self.p].is_ascii_digit() && self.float_literal(self.p - 1) {
+                    return;
+                }
+                self.tk = Dot;
+                return;
+            }
+            // Handle conditional (ternary) operator
+            else if c == b'?' {
+                self.tk = Cond;
+                return;
+            }
+            // Handle single-character tokens (e.g., ~, ;, {, }, (, ), ], ,, :)
+            else if matches!(c, b'~' | b';' | b'{' | b'}' | b'(' | b')' | b']' | b',' | b':') {
+                return;
+            }
+        }
+    }
+
+    // Parses the suffix of an integer literal whose value has been read (u, l, ll, in
+    // either case and order) and returns its type: the first of int, unsigned int, long
+    // and unsigned long that the suffix allows and that can hold the value. A decimal
+    // literal without 'u' is only unsigned if it does not fit in a long.
+    fn literal_type(&mut self, decimal: bool) -> Ty {
+        let (mut unsigned, mut long) = (false, false);
+        loop {
+            match self.src[self.p] {
+                b'u' | b'U' if !unsigned => unsigned = true,
+                b'l' | b'L' if !long => {
+                    long = true;
+                    if self.src[self.p + 1] == self.src[self.p] {
+                        self.p += 1; // long long is long
+                    }
+                }
+                _ => break,
+            }
+            self.p += 1;
+        }
+        let v = self.ival as u64;
+        let candidates = [(INT, !long && !unsigned), (UINT, !long && (unsigned || !decimal)), (LONG, !unsigned), (ULONG, true)];
+        let max = |t| match t {
+            INT => i32::MAX as u64,
+            UINT => u32::MAX as u64,
+            LONG => i64::MAX as u64,
+            _ => u64::MAX,
+        };
+        candidates.into_iter().find(|&(t, allowed)| allowed && v <= max(t)).map_or(ULONG, |(t, _)| t)
+    }
+
+    // Reads a floating-point literal starting at `start`, if the digits there are
+    // followed by a fraction or an exponent, and returns true if there was one. Its
+    // value is kept as the bits of a double; an 'f' suffix makes it a float, rounded to
+    // float precision, and an 'l' suffix a long double, which is a double here.
+    fn float_literal(&mut self, start: usize) -> bool {
+        let digits = |s: &[u8], mut p: usize| {
+            while s[p].is_ascii_digit() {
+                p += 1;
+            }
+            p
+        };
+        let mut p = digits(&self.src, start);
+        let fraction = self.src[p] == b'.';
+        if fraction {
+            p = digits(&self.src, p + 1);
+        }
+        let mut end = p;
+        if self.src[p] == b'e' || self.src[p] == b'E' {
+            let sign = matches!(self.src[p + 1], b'+' | b'-') as usize;
+            if self.src[p + 1 + sign].is_ascii_digit() {
+                end = digits(&self.src, p + 1 + sign);
+            }
+        }
+        if !fraction && end == p {
+            return false;
+        }
+        let x: f64 = std::str::from_utf8(&self.src[start..end]).ok().and_then(|t| t.parse().ok()).unwrap_or(0.0);
+        self.p = end;
+        self.num_ty = DOUBLE;
+        match self.src[self.p] {
+            b'f' | b'F' => {
+                self.p += 1;
+                self.num_ty = FLOAT;
+                self.ival = bits(x as f32 as f64);
+            }
+            b'l' | b'L' => {
+                self.p += 1;
+                self.ival = bits(x);
+            }
+            _ => self.ival = bits(x),
+        }
+        self.tk = Num;
+        true
+    }
+
+    // Emits the load matching the type of the value whose address is in the accumulator
+    // (a struct, union, array or function is left as its address, and void has no value)
+    fn emit_load(&mut self) {
+        let op = match self.types.kind(self.ty) {
+            TypeKind::Record(_) | TypeKind::Array(..) | TypeKind::Func(_) | TypeKind::Void => return,
+            TypeKind::Char => LC,
+            TypeKind::UChar => LCU,
+            TypeKind::Short => LS,
+            TypeKind::UShort => LSU,
+            TypeKind::Int => LW,
+            TypeKind::UInt => LWU,
+            TypeKind::Float => LF,
+            _ => LI,
+        };
+        self.emit(op);
+        self.load = Some(self.e);
+    }
+
+    // Returns true if the last word emitted is the load of an lvalue, which its address
+    // can be recovered from
+    fn ends_in_load(&self) -> bool {
+        self.load == Some(self.e)
+    }
+
+    // Emits the store matching the size of the current type
+    fn emit_store(&mut self) {
+        self.emit(match self.types.size(self.ty) {
+            _ if self.ty == FLOAT => SF,
+            1 => SC,
+            2 => SS,
+            4 => SW,
+            _ => SI,
+        });
+    }
+
+    // Emits the extension that wraps the accumulator into the range of integer type `t`,
+    // if it is smaller than a word
+    fn emit_narrow(&mut self, t: Ty) {
+        let size = self.types.size(t);
+        if self.types.is_integer(t) && size < WORD_SIZE {
+            self.emit(if self.types.is_unsigned(t) { ZEXT } else { SEXT });
+            self.emit(size);
+        }
+    }
+
+    // Converts the value in the accumulator from the current type to `to`, which becomes
+    // the current type. Among integers only a conversion into a smaller or differently
+    // signed type changes the value; integers are always kept within the range of their
+    // type. Floats are held as doubles rounded to float precision, so a float becomes a
+    // double as it is.
+    fn emit_convert(&mut self, to: Ty) {
+        let from = self.types.decay(self.ty);
+        if self.types.is_float(from) || self.types.is_float(to) {
+            if !self.types.is_float(from) {
+                self.emit(if from == ULONG || self.types.is_ptr(from) { UTOF } else { ITOF });
+            } else if !self.types.is_float(to) {
+                self.emit(if to == ULONG || self.types.is_ptr(to) { FTOU } else { FTOI });
+                self.emit_narrow(to);
+            }
+            if to == FLOAT && from != FLOAT {
+                self.emit(DTOF);
+            }
+            self.ty = to;
+            return;
+        }
+        let (size, unsigned) = (self.types.size(from), self.types.is_unsigned(from));
+        let fits = self.types.is_integer(from)
+            && (size < self.types.size(to) && (unsigned || !self.types.is_unsigned(to))
+                || size == self.types.size(to) && unsigned == self.types.is_unsigned(to));
+        if !fits {
+            self.emit_narrow(to);
+        }
+        self.ty = to;
+    }
+
+    // Emits arithmetic operator `op` on the pushed left operand, of type `left`, and the
+    // right operand in the accumulator, of the current type, in their common type.
+    // The left operand can no longer be converted: values already in range need no
+    // code, results are wrapped into the common type afterwards, DIVU and MODU only
+    // look at the common type's width, and for comparisons in unsigned int the right
+    // operand takes the left one's type instead, as -1 and 0xffffffff compare the same
+    // way as unsigned words as they do as unsigned ints. In a floating-point type, the
+    // left operand is converted by swapping it into the accumulator and back.
+    fn emit_arith(&mut self, op: OpCode, left: Ty, at: Span) -> Result<(), CompileError> {
+        let right = self.types.decay(self.ty);
+        let common = self.types.common(left, right);
+        if self.types.is_float(common) {
+            let arithmetic = self.types.is_arithmetic(left) && self.types.is_arithmetic(right);
+            if !arithmetic || !matches!(op, ADD | SUB | MUL | DIV | EQ | NE | LT | GT | LE | GE) {
+                return Err(CompileError::new(ErrorKind::BadOperands, at));
+            }
+            self.emit_convert(common);
+            if !self.types.is_float(left) {
+                self.emit(SWAP);
+                self.ty = left;
+                self.emit_convert(common);
+                self.emit(SWAP);
+            }
+            self.emit(match op {
+                ADD => FADD,
+                SUB => FSUB,
+                MUL => FMUL,
+                DIV => FDIV,
+                EQ => FEQ,
+                NE => FNE,
+                LT => FLT,
+                GT => FGT,
+                LE => FLE,
+                _ => FGE,
+            });
+            if matches!(op, ADD | SUB | MUL | DIV) {
+                if common == FLOAT {
+                    self.emit(DTOF);
+                }
+                self.ty = common;
+            } else {
+                self.ty = INT;
+            }
+            return Ok(());
+        }
+        let size = self.types.size(common);
+        let unsigned = self.types.is_unsigned(common);
+        match op {
+            EQ | NE | LT | GT | LE | GE => {
+                self.emit_convert(if size < WORD_SIZE { self.types.promote(left) } else { common });
+                self.emit(match op {
+                    LT if unsigned => LTU,
+                    GT if unsigned => GTU,
+                    LE if unsigned => LEU,
+                    GE if unsigned => GEU,
+                    _ => op,
+                });
+                self.ty = INT;
+            }
+            // The result has the type of the left operand
+            SHL | SHR => {
+                let ty = if self.types.is_integer(left) { self.types.promote(left) } else { ULONG };
+                if op == SHR && self.types.is_unsigned(ty) {
+                    self.emit(SHRU);
+                } else {
+                    self.emit(op);
+                }
+                if op == SHL {
+                    self.emit_narrow(ty);
+                }
+                self.ty = ty;
+            }
+            DIV | MOD => {
+                if unsigned {
+                    self.emit(if op == DIV { DIVU } else { MODU });
+                    self.emit(size);
+                } else {
+                    self.emit(op);
+                }
+                self.ty = common;
+            }
+            _ => {
+                self.emit(op);
+                // Bitwise operators on values of the same signedness stay in range
+                let mixed = self.types.promote(left) != self.types.promote(right);
+                if matches!(op, ADD | SUB | MUL) || mixed {
+                    self.emit_narrow(common);
+                }
+                self.ty = common;
+            }
+        }
+        Ok(())
+    }
+
+    // Turns a floating-point value in the accumulator into 0 or 1 for a branch on it,
+    // as -0.0 is false but not all zero bits
+    fn emit_truth(&mut self) {
+        if self.types.is_float(self.ty) {
+            self.emit(PSH);
+            self.emit(IMM);
+            self.emit(0);
+            self.emit(FNE);
+            self.ty = INT;
+        }
+    }
+
+    // Steps the value in the accumulator, of the current type, for ++ or --: by the
+    // size of what a pointer points to, or by 1
+    fn emit_step(&mut self, inc: bool) {
+        self.emit(PSH);
+        self.emit(IMM);
+        if self.types.is_float(self.ty) {
+            self.emit(bits(1.0));
+            self.emit(if inc { FADD } else { FSUB });
+            if self.ty == FLOAT {
+                self.emit(DTOF);
+            }
+        } else {
+            self.emit(self.stride(self.ty));
+            self.emit(if inc { ADD } else { SUB });
+            self.emit_narrow(self.ty);
+        }
+    }
+
+    // Size of the values a pointer of type `t` steps over, or 1 for non-pointers
+    fn stride(&self, t: Ty) -> i64 {
+        self.types.deref(t).map_or(1, |to| self.types.size(to))
+    }
+
+    // Emits a multiplication of the accumulator by `n`, if it is not 1
+    fn emit_scale(&mut self, n: i64) {
+        if n != 1 {
+            self.emit(PSH);
+            self.emit(IMM);
+            self.emit(n);
+            self.emit(MUL);
+        }
+    }
+
+    // Parses and generates code for an expression, climbing operators of precedence >= lev
+    fn expr(&mut self, lev: i64) -> Result<(), CompileError> {
+        // Temporary variable for storing type or token values
+        let t: i64;
+        // Index for tracking jump addresses
+        let mut d: usize;
+
+        // Handle unexpected end of input
+        if self.tk == 0 {
+            return Err(self.error(ErrorKind::UnexpectedEof));
+        }
+        // Handle numeric literals
+        else if self.tk == Num {
+            self.emit(IMM); // Emit immediate value opcode
+            self.emit(self.ival); // Store the numeric value
+            self.ty = self.num_ty; // Set type of the literal
+            self.next(); // Move to next token
+        }
+        // Handle string literals
+        else if self.tk == b'"' as i64 {
+            self.emit(IMM); // Emit immediate value opcode
+            self.emit(self.ival); // Store string address
+            self.next();
+            // Handle concatenated strings
+            while self.tk == b'"' as i64 {
+                self.next();
+            }
+            // Terminate with NUL padding up to the next int boundary
+            self.alloc_data(WORD_SIZE as usize - self.dp % WORD_SIZE as usize);
+            self.ty = self.types.ptr(CHAR); // Set type to pointer
+        }
+        // Handle sizeof operator, applied to a type name or to an expression
+        else if self.tk == Sizeof {
+            let at = self.span();
+            self.next();
+            let paren = self.tk == b'(' as i64;
+            if paren {
+                self.next();
+            }
+            if paren && self.is_type() {
+                self.ty = self.type_name()?;
+            } else {
+                // Only the type of the expression matters: drop its code, and with it the
+                // calls it makes, which need no definition
+                let start = self.e;
+                let calls = self.calls.len();
+                if paren {
+                    self.comma_expr()?;
+                } else {
+                    self.expr(Inc)?;
+                }
+                self.e = start;
+                self.calls.truncate(calls);
+            }
+            if paren {
+                if self.tk == b')' as i64 {
+                    self.next();
+                } else {
+                    return Err(self.error(ErrorKind::MissingCloseParen(Some("sizeof"))));
+                }
+            }
+            if !self.types.is_complete(self.ty) {
+                return Err(CompileError::new(ErrorKind::IncompleteType, at));
+            }
+            self.emit(IMM); // Emit immediate value
+            self.emit(self.types.size(self.ty)); // Emit size
+            self.ty = ULONG; // Result is unsigned long, as size_t
+        }
+        // Handle identifiers (variables, functions, constants)
+        else if self.tk == Id {
+            let s = self.id; // Save identifier
+            let at = self.span();
+            self.next();
+            // Call of a named function; variables holding function pointers are called
+            // through the accumulator like any other expression
+            if self.tk == b'(' as i64 && self.sym[s].class != Loc && self.sym[s].class != Glo {
+                if self.sym[s].class == 0 {
+                    let name = self.sym[s].name.clone();
+                    return Err(CompileError::new(ErrorKind::UndeclaredFunction(name), at));
+                } else if self.sym[s].class != Fun && self.sym[s].class != Sys {
+                    return Err(CompileError::new(ErrorKind::BadFunctionCall, at));
+                }
+                self.next();
+                let params = self.sym[s].params.clone(); // Parameter types, if declared
+                let n = self.arguments(params.as_deref())?;
+                // The number of arguments must match the declaration
+                if let Some(params) = params {
+                    let name = self.sym[s].name.clone();
+                    if let Err(err) = self.check_arity(params.len(), self.sym[s].variadic, n, Some(name), at) {
+                        return Err(match self.sym[s].span {
+                            Some(decl) => err.with_note("declared here", decl),
+                            None => err,
+                        });
+                    }
+                }
+                // Handle system call or function call
+                if self.sym[s].class == Sys {
+                    self.emit(self.sym[s].val); // Emit syscall
+                } else {
+                    self.emit(JSR); // Emit jump to subroutine
+                    self.emit(self.sym[s].val); // Function address
+                    // Only declared so far: patched when the definition is seen
+                    if self.sym[s].val == 0 {
+                        self.calls.push((s, self.e, at));
+                    }
+                }
+                // Adjust stack for arguments
+                if n != 0 {
+                    self.emit(ADJ);
+                    self.emit(n as i64);
+                }
+                self.ty = self.sym[s].ty; // Set return type
+            }
+            // Numeric constant
+            else if self.sym[s].class == Num {
+                self.emit(IMM);
+                self.emit(self.sym[s].val);
+                self.ty = INT;
+            }
+            // Function used as a value: its address, of function type
+            else if self.sym[s].class == Fun {
+                self.emit(IMM);
+                self.emit(self.sym[s].val);
+                if self.sym[s].val == 0 {
+                    self.calls.push((s, self.e, at));
+                }
+                let params = self.sym[s].params.clone();
+                self.ty = self.types.func(self.sym[s].ty, params, self.sym[s].variadic);
+            } else if self.sym[s].class == Sys {
+                let name = self.sym[s].name.clone();
+                return Err(CompileError::new(ErrorKind::BuiltinAddress(name), at));
+            }
+            // Variable (local or global)
+            else {
+                if self.sym[s].class == Loc {
+                    self.emit(LEA); // Load effective address
+                    self.emit(self.loc - self.sym[s].val); // Local offset
+                } else if self.sym[s].class == Glo {
+                    self.emit(IMM); // Immediate address
+                    self.emit(self.sym[s].val); // Global address
+                } else if self.sym[s].class == Typedef {
+                    return Err(CompileError::new(ErrorKind::BadExpression, at));
+                } else {
+                    return Err(CompileError::new(ErrorKind::UndefinedVariable, at));
+                }
+                self.ty = self.sym[s].ty; // Set variable type
+                self.emit_load(); // Load char or int
+            }
+        }
+        // Handle type casts or parenthesized expressions
+        else if self.tk == b'(' as i64 {
+            self.next();
+            if self.is_type() {
+                let at = self.span();
+                let t = self.type_name()?;
+                if self.tk == b')' as i64 {
+                    self.next();
+                } else {
+                    return Err(self.error(ErrorKind::BadCast));
+                }
+                // Only scalar types can be cast to
+                if self.types.is_record(t) || self.types.is_array(t) || self.types.is_func(t) {
+                    return Err(CompileError::new(ErrorKind::BadCast, at));
+                }
+                // A cast to void discards the value, if there is one
+                if t == VOID {
+                    self.expr(Inc)?;
+                    self.ty = VOID;
+                } else {
+                    self.value(Inc)?; // Parse expression
+                    // Pointers and floating-point values do not convert into each other
+                    let from = self.types.decay(self.ty);
+                    if self.types.is_float(t) && !self.types.is_arithmetic(from) || self.types.is_float(from) && !self.types.is_arithmetic(t) {
+                        return Err(CompileError::new(ErrorKind::BadCast, at));
+                    }
+                    self.emit_convert(t); // Convert to cast type
+                }
+            } else {
+                self.comma_expr()?; // Parse parenthesized expression
+                if self.tk == b')' as i64 {
+                    self.next();
+                } else {
+                    return Err(self.error(ErrorKind::MissingCloseParen(None)));
+                }
+            }
+        }
+        // Handle dereference operator (*)
+        else if self.tk == Mul {
+            let at = self.span();
+            self.next();
+            self.value(Inc)?; // Parse expression
+            let t = self.types.decay(self.ty);
+            match self.types.deref(t) {
+                Some(t) => self.ty = t, // Decrease pointer level
+                None => return Err(CompileError::new(ErrorKind::BadDereference, at)),
+            }
+            self.emit_load(); // Load value
+        }
+        // Handle address-of operator (&)
+        else if self.tk == And {
+            let at = self.span();
+            self.next();
+            self.expr(Inc)?; // Parse expression
+            if self.ends_in_load() {
+                self.e -= 1; // Remove load instruction
+            } else if !self.types.is_record(self.ty) && !self.types.is_array(self.ty) && !self.types.is_func(self.ty) {
+                // Structs, unions, arrays and functions are already represented by their address
+                return Err(CompileError::new(ErrorKind::BadAddressOf, at));
+            }
+            self.ty = self.types.ptr(self.ty); // Increase pointer level
+        }
+        // Handle logical NOT operator (!)
+        else if self.tk == b'!' as i64 {
+            let at = self.span();
+            self.next();
+            self.value(Inc)?; // Parse expression
+            let t = self.types.decay(self.ty);
+            self.emit(PSH);
+            self.emit(IMM);
+            self.emit(0);
+            self.ty = INT;
+            self.emit_arith(EQ, t, at)?; // Compare with 0
+        }
+        // Handle bitwise NOT operator (~)
+        else if self.tk == b'~' as i64 {
+            let at = self.span();
+            self.next();
+            self.value(Inc)?; // Parse expression
+            let t = self.types.decay(self.ty);
+            self.emit(PSH);
+            self.emit(IMM);
+            self.emit(-1);
+            self.ty = INT;
+            self.emit_arith(XOR, t, at)?; // XOR with -1
+        }
+        // Handle unary plus operator (+)
+        else if self.tk == Add {
+            self.next();
+            self.value(Inc)?; // Parse expression
+            if self.types.is_integer(self.ty) {
+                self.ty = self.types.promote(self.ty);
+            }
+        }
+        // Handle unary minus operator (-)
+        else if self.tk == Sub {
+            let at = self.span();
+            self.next();
+            self.emit(IMM);
+            if self.tk == Num && self.types.is_float(self.num_ty) {
+                self.emit(bits(-float(self.ival))); // Negate constant directly
+                self.ty = self.num_ty;
+                self.next();
+            } else if self.tk == Num {
+                self.emit(self.types.wrap(self.num_ty, self.ival.wrapping_neg())); // Negate constant directly
+                self.ty = self.num_ty;
+                self.next();
+            } else {
+                self.emit(-1);
+                self.emit(PSH);
+                self.value(Inc)?; // Parse expression
+                self.emit_arith(MUL, INT, at)?; // Multiply by -1
+            }
+        }
+        // Handle pre-increment/decrement operators
+        else if self.tk == Inc || self.tk == Dec {
+            t = self.tk;
+            let at = self.span();
+            self.next();
+            self.expr(Inc)?; // Parse expression
+            if self.ends_in_load() {
+                let load = self.code[self.e];
+                self.code[self.e] = PSH;
+                self.emit(load);
+            } else {
+                return Err(CompileError::new(ErrorKind::BadLvalue(LvalueContext::PreIncrement), at));
+            }
+            self.emit_step(t == Inc);
+            self.emit_store(); // Store result
+        } else {
+            return Err(self.error(ErrorKind::BadExpression));
+        }
+
+        // Handle binary operators based on precedence, and calls, which bind as tightly as
+        // the other postfix operators
+        while self.tk >= lev || self.tk == b'(' as i64 {
+            // Every operator uses the value on its left
+            if self.ty == VOID {
+                return Err(self.error(ErrorKind::VoidValue));
+            }
+            // Arrays and functions are used through a pointer
+            let t = self.types.decay(self.ty); // Save current type
+            // Assignment operator
+            if self.tk == Assign {
+                if self.types.is_record(t) {
+                    return Err(self.error(ErrorKind::AggregateByValue));
+                }
+                if self.ends_in_load() {
+                    self.code[self.e] = PSH; // Push address
+                } else {
+                    return Err(self.error(ErrorKind::BadLvalue(LvalueContext::Assignment)));
+                }
+                self.next();
+                self.value(Assign)?; // Parse right-hand side
+                self.emit_convert(t);
+                self.emit_store(); // Store value
+            }
+            // Compound assignment (+=, -=, ...): the address is computed once, pushed
+            // for the store and loaded from again for the operation
+            else if self.tk > Assign && self.tk <= XorAssign {
+                let op = match self.tk {
+                    AddAssign => ADD,
+                    SubAssign => SUB,
+                    MulAssign => MUL,
+                    DivAssign => DIV,
+                    ModAssign => MOD,
+                    ShlAssign => SHL,
+                    ShrAssign => SHR,
+                    AndAssign => AND,
+                    OrAssign => OR,
+                    _ => XOR,
+                };
+                let load = self.code[self.e];
+                if self.ends_in_load() {
+                    self.code[self.e] = PSH; // Push address
+                    self.emit(load); // Load current value
+                } else {
+                    return Err(self.error(ErrorKind::BadLvalue(LvalueContext::Assignment)));
+                }
+                let at = self.span();
+                self.next();
+                self.emit(PSH);
+                self.value(Assign)?; // Parse right-hand side
+                // Pointer arithmetic
+                if self.types.is_ptr(t) && (op == ADD || op == SUB) {
+                    if self.types.is_float(self.ty) {
+                        return Err(CompileError::new(ErrorKind::BadOperands, at));
+                    }
+                    self.emit_scale(self.stride(t));
+                    self.emit(op);
+                } else {
+                    self.emit_arith(op, t, at)?;
+                }
+                self.emit_convert(t);
+                self.emit_store(); // Store result
+            }
+            // Ternary conditional operator (?:)
+            else if self.tk == Cond {
+                self.next();
+                self.emit_truth();
+                self.emit(BZ); // Branch if zero
+                self.emit(0);
+                d = self.e; // Save jump address
+                self.comma_expr()?; // Parse true expression
+                let yes = self.types.decay(self.ty);
+                if self.tk == b':' as i64 {
+                    self.next();
+                } else {
+                    return Err(self.error(ErrorKind::MissingColon));
+                }
+                self.code[d] = (self.e + 3) as i64; // Patch jump
+                self.emit(JMP); // Jump to end
+                self.emit(0);
+                d = self.e; // Save jump address
+                self.expr(Cond)?; // Parse false expression
+                // With a floating-point operand, both become their common type; the true
+                // one is converted after the false one, which jumps over its conversion
+                let no = self.types.decay(self.ty);
+                let mut stub = None; // Conversion of the true expression, if it needs one
+                if (self.types.is_float(yes) || self.types.is_float(no)) && self.types.is_arithmetic(yes) && self.types.is_arithmetic(no) {
+                    let common = self.types.common(yes, no);
+                    self.emit_convert(common);
+                    self.emit(JMP); // Jump over the conversion to the end
+                    self.emit(0);
+                    let skip = self.e;
+                    self.ty = yes;
+                    self.emit_convert(common);
+                    if self.e == skip {
+                        self.e -= 2; // Nothing to convert
+                    } else {
+                        self.code[skip] = (self.e + 1) as i64;
+                        stub = Some(skip + 1);
+                    }
+                }
+                self.code[d] = stub.unwrap_or(self.e + 1) as i64; // Patch jump
+            }
+            // Logical OR (||)
+            else if self.tk == Lor {
+                self.next();
+                self.emit_truth();
+                self.emit(BNZ); // Branch if non-zero
+                self.emit(0);
+                d = self.e;
+                self.value(Lan)?; // Parse right-hand side
+                self.emit_truth();
+                self.code[d] = (self.e + 1) as i64; // Patch jump
+                self.ty = INT;
+            }
+            // Logical AND (&&)
+            else if self.tk == Lan {
+                self.next();
+                self.emit_truth();
+                self.emit(BZ); // Branch if zero
+                self.emit(0);
+                d = self.e;
+                self.value(Or)?; // Parse right-hand side
+                self.emit_truth();
+                self.code[d] = (self.e + 1) as i64; // Patch jump
+                self.ty = INT;
+            }
+            // Bitwise OR (|)
+            else if self.tk == Or {
+                self.binary(Xor, OR, t)?;
+            }
+            // Bitwise XOR (^)
+            else if self.tk == Xor {
+                self.binary(And, XOR, t)?;
+            }
+            // Bitwise AND (&)
+            else if self.tk == And {
+                self.binary(Eq, AND, t)?;
+            }
+            // Equality (==)
+            else if self.tk == Eq {
+                self.binary(Lt, EQ, t)?;
+            }
+            // Inequality (!=)
+            else if self.tk == Ne {
+                self.binary(Lt, NE, t)?;
+            }
+            // Less than (<)
+            else if self.tk == Lt {
+                self.binary(Shl, LT, t)?;
+            }
+            // Greater than (>)
+            else if self.tk == Gt {
+                self.binary(Shl, GT, t)?;
+            }
+            // Less than or equal (<=)
+            else if self.tk == Le {
+                self.binary(Shl, LE, t)?;
+            }
+            // Greater than or equal (>=)
+            else if self.tk == Ge {
+                self.binary(Shl, GE, t)?;
+            }
+            // Left shift (<<)
+            else if self.tk == Shl {
+                self.binary(Add, SHL, t)?;
+            }
+            // Right shift (>>)
+            else if self.tk == Shr {
+                self.binary(Add, SHR, t)?;
+            }
+            // Addition (+)
+            else if self.tk == Add {
+                let at = self.span();
+                self.next();
+                self.emit(PSH);
+                self.value(Mul)?;
+                if self.types.is_ptr(t) && self.types.is_float(self.ty) || self.types.is_float(t) && self.types.is_ptr(self.ty) {
+                    return Err(CompileError::new(ErrorKind::BadOperands, at));
+                } else if self.types.is_ptr(t) {
+                    // Handle pointer arithmetic
+                    self.emit_scale(self.stride(t));
+                    self.emit(ADD);
+                    self.ty = t;
+                } else if self.types.is_ptr(self.ty) {
+                    self.emit(ADD); // The integer on the left is not scaled
+                    self.ty = t;
+                } else {
+                    self.emit_arith(ADD, t, at)?;
+                }
+            }
+            // Subtraction (-)
+            else if self.tk == Sub {
+                let at = self.span();
+                self.next();
+                self.emit(PSH);
+                self.value(Mul)?;
+                if self.types.is_ptr(t) && self.types.is_float(self.ty) {
+                    return Err(CompileError::new(ErrorKind::BadOperands, at));
+                } else if self.types.is_ptr(t) && t == self.types.decay(self.ty) {
+                    // Pointer subtraction
+                    self.emit(SUB);
+                    if self.stride(t) != 1 {
+                        self.emit(PSH);
+                        self.emit(IMM);
+                        self.emit(self.stride(t));
+                        self.emit(DIV);
+                    }
+                    self.ty = LONG;
+                } else if self.types.is_ptr(t) {
+                    // Pointer arithmetic
+                    self.emit_scale(self.stride(t));
+                    self.emit(SUB);
+                    self.ty = t;
+                } else {
+                    self.emit_arith(SUB, t, at)?;
+                }
+            }
+            // Multiplication (*)
+            else if self.tk == Mul {
+                self.binary(Inc, MUL, t)?;
+            }
+            // Division (/)
+            else if self.tk == Div {
+                self.binary(Inc, DIV, t)?;
+            }
+            // Modulo (%)
+            else if self.tk == Mod {
+                self.binary(Inc, MOD, t)?;
+            }
+            // Post-increment/decrement
+            else if self.tk == Inc || self.tk == Dec {
+                if self.ends_in_load() {
+                    let load = self.code[self.e];
+                    self.code[self.e] = PSH;
+                    self.emit(load);
+                } else {
+                    return Err(self.error(ErrorKind::BadLvalue(LvalueContext::PostIncrement)));
+                }
+                if self.types.is_float(self.ty) {
+                    // Stepping back would round: keep the original value on the stack,
+                    // under a second copy of the address
+                    let load = self.code[self.e];
+                    self.emit(SWAP);
+                    self.emit(PSH);
+                    self.emit(load);
+                    self.emit_step(self.tk == Inc);
+                    self.emit_store();
+                    self.emit(SWAP);
+                    self.emit(ADJ);
+                    self.emit(1);
+                } else {
+                    self.emit_step(self.tk == Inc);
+                    self.emit_store();
+                    // Restore original value
+                    self.emit_step(self.tk != Inc);
+                }
+                self.next();
+            }
+            // Array subscript
+            else if self.tk == Brak {
+                let at = self.span();
+                self.next();
+                self.emit(PSH);
+                self.comma_value()?; // Parse index
+                if self.tk == b']' as i64 {
+                    self.next();
+                } else {
+                    return Err(self.error(ErrorKind::MissingCloseBracket));
+                }
+                let Some(elem) = self.types.deref(t) else {
+                    return Err(CompileError::new(ErrorKind::PointerExpected, at));
+                };
+                self.emit_scale(self.types.size(elem));
+                self.emit(ADD); // Add offset
+                self.ty = elem; // Set element type
+                self.emit_load(); // Load value
+            }
+            // Member access (. and ->): add the member's offset to the record's address
+            else if self.tk == Dot || self.tk == Arrow {
+                let at = self.span();
+                let record = if self.tk == Dot { Some(t) } else { self.types.deref(t) };
+                let record = record.filter(|r| self.types.is_record(*r));
+                let Some(record) = record else {
+                    return Err(CompileError::new(ErrorKind::BadMemberAccess, at));
+                };
+                self.next();
+                if self.tk != Id {
+                    return Err(self.error(ErrorKind::BadMemberAccess));
+                }
+                let Some(field) = self.types.field(record, self.id) else {
+                    let name = self.sym[self.id].name.clone();
+                    return Err(self.error(ErrorKind::NoSuchMember(name)));
+                };
+                let (offset, ty) = (field.offset, field.ty);
+                self.next();
+                if offset != 0 {
+                    self.emit(PSH);
+                    self.emit(IMM);
+                    self.emit(offset);
+                    self.emit(ADD);
+                }
+                self.ty = ty;
+                self.emit_load(); // Load member
+            }
+            // Call through a function pointer: its address is pushed before the arguments,
+            // where JSRI finds it, and popped with them
+            else if self.tk == b'(' as i64 {
+                let at = self.span();
+                let sig = self.types.deref(t).and_then(|f| self.types.signature(f)).cloned();
+                let Some(sig) = sig else {
+                    return Err(CompileError::new(ErrorKind::BadFunctionCall, at));
+                };
+                self.next();
+                self.emit(PSH);
+                let n = self.arguments(sig.params.as_deref())?;
+                if let Some(params) = &sig.params {
+                    self.check_arity(params.len(), sig.variadic, n, None, at)?;
+                }
+                self.emit(JSRI);
+                self.emit(n as i64);
+                self.emit(ADJ);
+                self.emit(n as i64 + 1);
+                self.ty = sig.ret;
+            }
+            // Unknown token
+            else {
+                return Err(self.error(ErrorKind::Internal(self.tk)));
+            }
+        }
+        Ok(())
+    }
+
+    // Parses the right operand of a simple binary operator and emits `op` on the pushed
+    // pair, the left operand being of type `left`
+    fn binary(&mut self, lev: i64, op: OpCode, left: Ty) -> Result<(), CompileError> {
+        let at = self.span();
+        self.next();
+        self.emit(PSH);
+        self.value(lev)?;
+        self.emit_arith(op, left, at)
+    }
+
+    // Parses and generates code for one statement
+    fn stmt(&mut self) -> Result<(), CompileError> {
+        // Handle 'if' statement
+        if self.tk == If {
+            self.next(); // Consume 'if'
+            self.expect(b'(', ErrorKind::MissingOpenParen(None))?;
+            self.comma_value()?; // Parse condition
+            self.emit_truth();
+            self.expect(b')', ErrorKind::MissingCloseParen(None))?;
+            // Emit branch-if-zero for false condition
+            self.emit(BZ);
+            self.emit(0);
+            let mut b = self.e; // Save address for jump patching
+            self.stmt()?; // Parse 'then' statement
+            // Handle 'else' clause
+            if self.tk == Else {
+                self.code[b] = (self.e + 3) as i64; // Patch BZ to skip else
+                self.emit(JMP); // Jump to end
+                self.emit(0);
+                b = self.e; // Save address for jump patching
+                self.next(); // Consume 'else'
+                self.stmt()?; // Parse 'else' statement
+            }
+            self.code[b] = (self.e + 1) as i64; // Patch jump to end
+        }
+        // Handle 'while' statement
+        else if self.tk == While {
+            self.next(); // Consume 'while'
+            let a = self.e + 1; // Mark loop start
+            self.expect(b'(', ErrorKind::MissingOpenParen(None))?;
+            self.comma_value()?; // Parse loop condition
+            self.emit_truth();
+            self.expect(b')', ErrorKind::MissingCloseParen(None))?;
+            // Emit branch-if-zero to exit loop
+            self.emit(BZ);
+            self.emit(0);
+            let b = self.e; // Save address for jump patching
+            let body = self.loop_body()?; // Parse loop body
+            // Emit jump back to loop start
+            self.emit(JMP);
+            self.emit(a as i64);
+            self.code[b] = (self.e + 1) as i64; // Patch BZ to loop end
+            self.patch_loop(body, a);
+        }
+        // Handle 'do' statement
+        else if self.tk == Do {
+            self.next(); // Consume 'do'
+            let a = self.e + 1; // Mark loop start
+            let body = self.loop_body()?; // Parse loop body
+            if self.tk != While {
+                return Err(self.error(ErrorKind::MissingWhile));
+            }
+            self.next(); // Consume 'while'
+            let c = self.e + 1; // Mark condition start
+            self.expect(b'(', ErrorKind::MissingOpenParen(None))?;
+            self.comma_value()?; // Parse loop condition
+            self.emit_truth();
+            self.expect(b')', ErrorKind::MissingCloseParen(None))?;
+            // Emit branch-if-nonzero back to loop start
+            self.emit(BNZ);
+            self.emit(a as i64);
+            self.expect(b';', ErrorKind::MissingSemicolon)?;
+            self.patch_loop(body, c);
+        }
+        // Handle 'for' statement
+        else if self.tk == For {
+            // A declaration in the first clause is in scope for the whole loop
+            let scope = self.open_scope();
+            let result = self.for_stmt();
+            self.close_scope(scope);
+            result?;
+        }
+        // Handle 'break' and 'continue' statements: emit a jump to be patched at the
+        // end of the innermost loop
+        else if self.tk == Break || self.tk == Continue {
+            // A break leaves the innermost loop or switch, a continue the innermost loop
+            let brk = self.tk == Break;
+            let target = if brk { self.loops.len().checked_sub(1) } else { self.loops.iter().rposition(|l| !l.switch) };
+            let Some(target) = target else {
+                return Err(self.error(if brk { ErrorKind::BreakOutsideLoop } else { ErrorKind::ContinueOutsideLoop }));
+            };
+            self.next(); // Consume 'break' or 'continue'
+            self.emit(JMP);
+            self.emit(0);
+            let site = self.e;
+            let l = &mut self.loops[target];
+            if brk {
+                l.breaks.push(site);
+            } else {
+                l.continues.push(site);
+            }
+            self.expect(b';', ErrorKind::MissingSemicolon)?;
+        }
+        // Handle 'switch' statement: the body comes first and the dispatch on the
+        // value, which is still in the accumulator, after it
+        else if self.tk == Switch {
+            self.next(); // Consume 'switch'
+            self.expect(b'(', ErrorKind::MissingOpenParen(None))?;
+            let at = self.span();
+            self.comma_value()?; // Parse switch value
+            if !self.types.is_integer(self.ty) {
+                return Err(CompileError::new(ErrorKind::SwitchNotInteger, at));
+            }
+            let ty = self.types.promote(self.ty);
+            self.expect(b')', ErrorKind::MissingCloseParen(None))?;
+            // Emit jump to the dispatch code
+            self.emit(JMP);
+            self.emit(0);
+            let d = self.e;
+            self.switches.push(Switch { ty, ..Switch::default() });
+            self.loops.push(Loop { switch: true, ..Loop::default() });
+            let result = self.stmt(); // Parse switch body
+            let mut body = self.loops.pop().unwrap_or_default();
+            let labels = self.switches.pop().unwrap_or_default();
+            result?;
+            // Emit jump past the dispatch code for the end of the body
+            self.emit(JMP);
+            self.emit(0);
+            body.breaks.push(self.e);
+            self.code[d] = (self.e + 1) as i64; // Patch jump to dispatch
+            self.dispatch(labels, &mut body.breaks);
+            self.patch_loop(body, 0);
+        }
+        // Handle 'goto' statement: jump now if the label is known, else patch it when
+        // the label is defined
+        else if self.tk == Goto {
+            self.next(); // Consume 'goto'
+            if self.tk != Id {
+                return Err(self.error(ErrorKind::BadLabel));
+            }
+            let at = self.span();
+            let l = self.label(self.id);
+            self.labels[l].used.get_or_insert(at);
+            self.next();
+            self.emit(JMP);
+            match self.labels[l].addr {
+                Some(addr) => self.emit(addr as i64),
+                None => {
+                    self.emit(0);
+                    self.labels[l].pending.push(self.e);
+                }
+            }
+            self.expect(b';', ErrorKind::MissingSemicolon)?;
+        }
+        // Handle labeled statement
+        else if self.tk == Id && self.peek() == b':' {
+            let at = self.span();
+            let l = self.label(self.id);
+            self.next(); // Consume label name
+            self.next(); // Consume ':'
+            if let Some(prev) = self.labels[l].span {
+                return Err(CompileError::new(ErrorKind::DuplicateLabel, at).with_note("previous definition was here", prev));
+            }
+            let addr = self.e + 1;
+            let label = &mut self.labels[l];
+            label.addr = Some(addr);
+            label.span = Some(at);
+            for site in std::mem::take(&mut label.pending) {
+                self.code[site] = addr as i64;
+            }
+            return self.labeled_stmt();
+        }
+        // Handle 'case' label
+        else if self.tk == Case {
+            let at = self.span();
+            self.next(); // Consume 'case'
+            let v = self.constant_expr()?;
+            self.expect(b':', ErrorKind::MissingColon)?;
+            let Some(sw) = self.switches.last_mut() else {
+                return Err(CompileError::new(ErrorKind::CaseOutsideSwitch, at));
+            };
+            // Compared as a value of the controlling expression's type
+            let v = self.types.wrap(sw.ty, v);
+            if let Some(&(_, _, prev)) = sw.cases.iter().find(|c| c.0 == v) {
+                return Err(CompileError::new(ErrorKind::DuplicateCase, at).with_note("previous case was here", prev));
+            }
+            sw.cases.push((v, self.e + 1, at));
+            return self.labeled_stmt();
+        }
+        // Handle 'default' label
+        else if self.tk == Default {
+            let at = self.span();
+            self.next(); // Consume 'default'
+            self.expect(b':', ErrorKind::MissingColon)?;
+            let Some(sw) = self.switches.last_mut() else {
+                return Err(CompileError::new(ErrorKind::DefaultOutsideSwitch, at));
+            };
+            if sw.default.is_some() {
+                return Err(CompileError::new(ErrorKind::DuplicateDefault, at));
+            }
+            sw.default = Some(self.e + 1);
+            return self.labeled_stmt();
+        }
+        // Handle 'return' statement
+        else if self.tk == Return {
+            let ret = self.span();
+            self.next(); // Consume 'return'
+            // Parse optional return value; a void function may only return a void one
+            if self.tk != b';' as i64 && self.ret == VOID {
+                self.comma_expr()?;
+                if self.ty != VOID {
+                    return Err(CompileError::new(ErrorKind::ReturnValueInVoid, ret));
+                }
+            } else if self.tk != b';' as i64 {
+                let at = self.span();
+                self.comma_value()?;
+                if self.types.is_record(self.ty) {
+                    return Err(CompileError::new(ErrorKind::AggregateByValue, at));
+                }
+                self.emit_convert(self.ret);
+            } else if self.ret != VOID {
+                self.warnings.push(CompileError::new(ErrorKind::ReturnWithoutValue, ret));
+            }
+            // Emit leave function instruction
+            self.emit(LEV);
+            self.expect(b';', ErrorKind::MissingSemicolon)?;
+        }
+        // Handle compound statement (block)
+        else if self.tk == b'{' as i64 {
+            self.next(); // Consume '{'
+            // Parse statements until closing brace, in a scope of their own
+            let scope = self.open_scope();
+            self.block_items();
+            self.close_scope(scope);
+            self.next(); // Consume '}'
+        }
+        // Handle empty statement
+        else if self.tk == b';' as i64 {
+            self.next(); // Consume ';'
+        }
+        // Handle expression statement
+        else {
+            self.comma_expr()?; // Parse expression
+            self.expect(b';', ErrorKind::MissingSemicolon)?;
+        }
+        Ok(())
+    }
+
+    // Returns the first non-blank character after the current token, without consuming it
+    fn peek(&self) -> u8 {
+        let mut p = self.p;
+        while matches!(self.src[p], b' ' | b'\t' | b'\r' | b'\n') {
+            p += 1;
+        }
+        self.src[p]
+    }
+
+    // Finds or creates the label named by symbol `id` in the current function
+    fn label(&mut self, id: usize) -> usize {
+        match self.labels.iter().position(|l| l.id == id) {
+            Some(l) => l,
+            None => {
+                self.labels.push(Label { id, addr: None, span: None, used: None, pending: Vec::new() });
+                self.labels.len() - 1
+            }
+        }
+    }
+
+    // Reports labels of the finished function that were never defined or never used
+    fn check_labels(&mut self) {
+        for label in std::mem::take(&mut self.labels) {
+            match (label.span, label.used) {
+                (None, Some(used)) => self.report(CompileError::new(ErrorKind::UndefinedLabel, used)),
+                (Some(span), None) => self.warnings.push(CompileError::new(ErrorKind::UnusedLabel, span)),
+                _ => {}
+            }
+        }
+    }
+
+    // Returns true if the end of the function whose code starts at `start` and ends at
+    // the current end of code can be reached other than through a return: each path is
+    // followed through the jumps and branches, a branch on a constant only one way
+    fn falls_off_end(&self, start: usize) -> bool {
+        let end = self.e + 1;
+        let mut seen = vec![false; end - start];
+        let mut paths = vec![start];
+        while let Some(mut pc) = paths.pop() {
+            let mut constant = None; // Value loaded by the previous instruction, if an IMM
+            while (start..end).contains(&pc) && !seen[pc - start] {
+                seen[pc - start] = true;
+                let op = self.code[pc];
+                let operand = self.code[pc + 1] as usize;
+                let next = pc + 1 + has_operand(op) as usize;
+                match op {
+                    LEV | EXIT => break,
+                    JMP => pc = operand,
+                    BZ | BNZ => {
+                        match constant {
+                            Some(v) if (v != 0) == (op == BNZ) => pc = operand,
+                            Some(_) => pc = next,
+                            None => {
+                                paths.push(operand);
+                                pc = next;
+                            }
+                        }
+                    }
+                    JTAB => {
+                        // The table holds the default target and then one per value
+                        let t = ((operand as i64 - self.data_addr(0)) / WORD_SIZE) as usize;
+                        let n = self.data[t + 1] as usize;
+                        paths.extend(self.data[t + 2..t + 3 + n].iter().map(|&a| a as usize));
+                        break;
+                    }
+                    _ => pc = next,
+                }
+                constant = if op == IMM { Some(operand as i64) } else { None };
+            }
+            if pc >= end {
+                return true;
+            }
+        }
+        false
+    }
+
+    // Parses the statement after a label; a label may also end a block
+    fn labeled_stmt(&mut self) -> Result<(), CompileError> {
+        if self.tk == b'}' as i64 {
+            return Ok(());
+        }
+        self.stmt()
+    }
+
+    // Emits the dispatch of a switch on the value in the accumulator: a jump table
+    // when the case values are dense, a chain of compare-and-branch otherwise. Jumps
+    // to the end of the switch are added to `breaks`.
+    fn dispatch(&mut self, mut labels: Switch, breaks: &mut Vec<usize>) {
+        labels.cases.sort_by_key(|c| c.0);
+        let n = labels.cases.len();
+        let (low, high) = match (labels.cases.first(), labels.cases.last()) {
+            (Some(first), Some(last)) => (first.0, last.0),
+            _ => (0, 0),
+        };
+        let range = (high as i128 - low as i128 + 1) as u128;
+        if n >= JTAB_MIN_CASES && range <= 2 * n as u128 {
+            // Table of [lowest value, entry count, default target, targets...] in the data segment
+            self.dp = (self.dp + 7) & !7;
+            let t = self.dp / WORD_SIZE as usize;
+            if !self.alloc_data((3 + range as usize) * WORD_SIZE as usize) {
+                return;
+            }
+            self.emit(JTAB);
+            self.emit(self.data_addr(t * WORD_SIZE as usize));
+            let default = labels.default.unwrap_or(self.e + 1);
+            self.data[t] = low;
+            self.data[t + 1] = range as i64;
+            self.data[t + 2] = default as i64;
+            for k in 0..range as usize {
+                self.data[t + 3 + k] = default as i64;
+            }
+            for (v, addr, _) in &labels.cases {
+                self.data[t + 3 + (v - low) as usize] = *addr as i64;
+            }
+        } else {
+            // Subtract the difference to each case value in turn and branch when zero
+            let mut prev = 0i64;
+            for (v, addr, _) in &labels.cases {
+                self.emit(PSH);
+                self.emit(IMM);
+                self.emit(v.wrapping_sub(prev));
+                self.emit(SUB);
+                self.emit(BZ);
+                self.emit(*addr as i64);
+                prev = *v;
+            }
+            self.emit(JMP);
+            match labels.default {
+                Some(addr) => self.emit(addr as i64),
+                None => {
+                    self.emit(0);
+                    breaks.push(self.e);
+                }
+            }
+        }
+    }
+
+    // Parses a 'for' statement: the step is compiled before the body, which is reached
+    // by jumping over it
+    fn for_stmt(&mut self) -> Result<(), CompileError> {
+        self.next(); // Consume 'for'
+        self.expect(b'(', ErrorKind::MissingOpenParen(None))?;
+        if self.is_type() {
+            self.local_declaration()?; // Parse declaration, including its ';'
+        } else {
+            if self.tk != b';' as i64 {
+                self.comma_expr()?; // Parse initialization
+            }
+            self.expect(b';', ErrorKind::MissingSemicolon)?;
+        }
+        let a = self.e + 1; // Mark condition start
+        let mut b = 0; // Address of the exit branch, if there is a condition
+        if self.tk != b';' as i64 {
+            self.comma_value()?; // Parse loop condition
+            self.emit_truth();
+            // Emit branch-if-zero to exit loop
+            self.emit(BZ);
+            self.emit(0);
+            b = self.e;
+        }
+        self.expect(b';', ErrorKind::MissingSemicolon)?;
+        let mut c = a; // Where each iteration ends up: the step, or else the condition
+        if self.tk != b')' as i64 {
+            // Emit jump over the step to the body
+            self.emit(JMP);
+            self.emit(0);
+            let d = self.e;
+            c = self.e + 1;
+            self.comma_expr()?; // Parse step expression
+            self.emit(JMP);
+            self.emit(a as i64);
+            self.code[d] = (self.e + 1) as i64; // Patch jump to body
+        }
+        self.expect(b')', ErrorKind::MissingCloseParen(None))?;
+        let body = self.loop_body()?; // Parse loop body
+        // Emit jump back to the step
+        self.emit(JMP);
+        self.emit(c as i64);
+        if b != 0 {
+            self.code[b] = (self.e + 1) as i64; // Patch BZ to loop end
+        }
+        self.patch_loop(body, c);
+        Ok(())
+    }
+
+    // Parses the arguments of a call up to and including its ')', pushing each one and
+    // checking it against its parameter's type when the parameters are known; returns
+    // the number of arguments
+    fn arguments(&mut self, params: Option<&[Ty]>) -> Result<usize, CompileError> {
+        let mut n = 0;
+        while self.tk != b')' as i64 {
+            let at = self.span();
+            let start = self.e;
+            self.value(Assign)?; // Parse argument expression
+            if self.types.is_record(self.ty) {
+                return Err(CompileError::new(ErrorKind::AggregateByValue, at));
+            }
+            if let Some(&ty) = params.and_then(|p| p.get(n)) {
+                self.check_argument(ty, start, n + 1, at)?;
+                self.emit_convert(ty);
+            }
+            self.emit(PSH); // Push argument
+            n += 1;
+            if self.tk == b',' as i64 {
+                self.next();
+            }
+        }
+        self.next();
+        Ok(n)
+    }
+
+    // Checks the number of arguments of a call at `at` against the number of parameters
+    // of the function, named unless it is called through a pointer
+    fn check_arity(&self, params: usize, variadic: bool, n: usize, name: Option<String>, at: Span) -> Result<(), CompileError> {
+        let kind = if n < params {
+            ErrorKind::TooFewArguments(name)
+        } else if n > params && !variadic {
+            ErrorKind::TooManyArguments(name)
+        } else {
+            return Ok(());
+        };
+        Err(CompileError::new(kind, at))
+    }
+
+    // Checks an argument whose code starts after `start` against the type of its
+    // parameter. Integers and pointers only convert into each other through a cast,
+    // except for a literal 0, which is a null pointer, and pointers only into pointers
+    // to compatible types or void; for these the argument is still passed. Floating-point
+    // values and pointers never convert into each other.
+    fn check_argument(&mut self, param: Ty, start: usize, n: usize, at: Span) -> Result<(), CompileError> {
+        let arg = self.types.decay(self.ty);
+        let null = self.e == start + 2 && self.code[start + 1] == IMM && self.code[start + 2] == 0;
+        let (to_ptr, from_ptr) = (self.types.is_ptr(param), self.types.is_ptr(arg));
+        let void_ptr = |t| self.types.deref(t) == Some(VOID);
+        let kind = if (to_ptr && self.types.is_float(arg)) || (from_ptr && self.types.is_float(param)) {
+            return Err(CompileError::new(ErrorKind::IncompatibleArgument(n), at));
+        } else if to_ptr && !from_ptr && !null {
+            ErrorKind::IntToPointer(n)
+        } else if !to_ptr && from_ptr {
+            ErrorKind::PointerToInt(n)
+        } else if to_ptr && from_ptr && !void_ptr(param) && !void_ptr(arg) && !self.types.compatible(param, arg) {
+            ErrorKind::IncompatiblePointer(n)
+        } else {
+            return Ok(());
+        };
+        self.warnings.push(CompileError::new(kind, at));
+        Ok(())
+    }
+
+    // Parses a full expression: assignment expressions separated by the comma operator,
+    // whose value and type are those of the last one
+    fn comma_expr(&mut self) -> Result<(), CompileError> {
+        self.expr(Assign)?;
+        while self.tk == b',' as i64 {
+            self.next();
+            self.expr(Assign)?;
+        }
+        Ok(())
+    }
+
+    // Parses an expression whose value is used, which a void expression does not have
+    fn value(&mut self, lev: i64) -> Result<(), CompileError> {
+        let at = self.span();
+        self.expr(lev)?;
+        self.check_value(at)
+    }
+
+    // Parses a full expression whose value is used
+    fn comma_value(&mut self) -> Result<(), CompileError> {
+        let at = self.span();
+        self.comma_expr()?;
+        self.check_value(at)
+    }
+
+    // Reports the void expression starting at `at` if the current type is void
+    fn check_value(&self, at: Span) -> Result<(), CompileError> {
+        if self.ty == VOID {
+            return Err(CompileError::new(ErrorKind::VoidValue, at));
+        }
+        Ok(())
+    }
+
+    // Parses an integer constant expression and returns its value
+    fn constant_expr(&mut self) -> Result<i64, CompileError> {
+        self.constant(None)
+    }
+
+    // Parses a constant expression, converted to type `to` if given and otherwise an
+    // integer one, and returns its value; the code compiled for it is evaluated and then
+    // discarded
+    fn constant(&mut self, to: Option<Ty>) -> Result<i64, CompileError> {
+        let at = self.span();
+        match self.address_constant(to)? {
+            (v, None) => Ok(v),
+            (_, Some(_)) => Err(CompileError::new(ErrorKind::NotConstant, at)),
+        }
+    }
+
+    // Like `constant`, but also accepts the address of a function that is not defined
+    // yet, returned with its location in place of the value, which is not known
+    fn address_constant(&mut self, to: Option<Ty>) -> Result<(i64, Option<(usize, Span)>), CompileError> {
+        let at = self.span();
+        let start = self.e;
+        let calls = self.calls.len();
+        self.value(Cond)?;
+        match to {
+            Some(to) => self.emit_convert(to),
+            None if self.types.is_float(self.ty) => {
+                self.e = start;
+                return Err(CompileError::new(ErrorKind::NotConstant, at));
+            }
+            None => {}
+        }
+        let end = self.e;
+        self.e = start;
+        // The address of a function that is not defined yet is not known, so it can
+        // only be the whole value
+        if self.calls.len() > calls {
+            let last = self.calls.pop().filter(|&(_, site, _)| self.calls.len() == calls && site == start + 2 && end == site);
+            self.calls.truncate(calls);
+            return match last {
+                Some((f, _, at)) => Ok((0, Some((f, at)))),
+                None => Err(CompileError::new(ErrorKind::NotConstant, at)),
+            };
+        }
+        let mut stack = Vec::new();
+        let mut a = 0i64;
+        let mut i = start + 1;
+        while i <= end {
+            let op = self.code[i];
+            i += 1;
+            if op == IMM {
+                a = self.code[i];
+                i += 1;
+                continue;
+            }
+            if op == PSH {
+                stack.push(a);
+                continue;
+            }
+            if op == SEXT || op == ZEXT {
+                a = extend(a, self.code[i], op == ZEXT);
+                i += 1;
+                continue;
+            }
+            if (ITOF..=DTOF).contains(&op) {
+                a = match op {
+                    ITOF => bits(a as f64),
+                    UTOF => bits(a as u64 as f64),
+                    FTOI => float(a) as i64,
+                    FTOU => float(a) as u64 as i64,
+                    _ => bits(float(a) as f32 as f64),
+                };
+                continue;
+            }
+            if op == SWAP {
+                let Some(top) = stack.last_mut() else {
+                    return Err(CompileError::new(ErrorKind::NotConstant, at));
+                };
+                std::mem::swap(top, &mut a);
+                continue;
+            }
+            let binary = (OR..=MOD).contains(&op) || (DIVU..=GEU).contains(&op) || (FADD..=FGE).contains(&op);
+            let b = stack.pop().filter(|_| binary);
+            let Some(b) = b else {
+                return Err(CompileError::new(ErrorKind::NotConstant, at));
+            };
+            // Width of the operands of DIVU and MODU
+            let n = if op == DIVU || op == MODU {
+                i += 1;
+                self.code[i - 1]
+            } else {
+                WORD_SIZE
+            };
+            let (ub, ua) = (extend(b, n, true) as u64, extend(a, n, true) as u64);
+            a = match op {
+                OR => b | a,
+                XOR => b ^ a,
+                AND => b & a,
+                EQ => (b == a) as i64,
+                NE => (b != a) as i64,
+                LT => (b < a) as i64,
+                GT => (b > a) as i64,
+                LE => (b <= a) as i64,
+                GE => (b >= a) as i64,
+                SHL => b.wrapping_shl(a as u32),
+                SHR => b.wrapping_shr(a as u32),
+                ADD => b.wrapping_add(a),
+                SUB => b.wrapping_sub(a),
+                MUL => b.wrapping_mul(a),
+                DIV if a != 0 => b.wrapping_div(a),
+                MOD if a != 0 => b.wrapping_rem(a),
+                DIVU if ua != 0 => (ub / ua) as i64,
+                MODU if ua != 0 => (ub % ua) as i64,
+                SHRU => ub.wrapping_shr(a as u32) as i64,
+                LTU => (ub < ua) as i64,
+                GTU => (ub > ua) as i64,
+                LEU => (ub <= ua) as i64,
+                GEU => (ub >= ua) as i64,
+                FADD => bits(float(b) + float(a)),
+                FSUB => bits(float(b) - float(a)),
+                FMUL => bits(float(b) * float(a)),
+                FDIV => bits(float(b) / float(a)),
+                FEQ => (float(b) == float(a)) as i64,
+                FNE => (float(b) != float(a)) as i64,
+                FLT => (float(b) < float(a)) as i64,
+                FGT => (float(b) > float(a)) as i64,
+                FLE => (float(b) <= float(a)) as i64,
+                FGE => (float(b) >= float(a)) as i64,
+                _ => return Err(CompileError::new(ErrorKind::NotConstant, at)),
+            };
+        }
+        Ok((a, None))
+    }
+
+    // Parses the body of a loop, collecting the breaks and continues inside it
+    fn loop_body(&mut self) -> Result<Loop, CompileError> {
+        self.loops.push(Loop::default());
+        let result = self.stmt();
+        let body = self.loops.pop().unwrap_or_default();
+        result.map(|_| body)
+    }
+
+    // Patches a loop's continues to `next` and its breaks to the current end of code
+    fn patch_loop(&mut self, body: Loop, next: usize) {
+        for site in body.continues {
+            self.code[site] = next as i64;
+        }
+        for site in body.breaks {
+            self.code[site] = (self.e + 1) as i64;
+        }
+    }
+
+    // Consumes the expected single-character token or reports `kind`
+    fn expect(&mut self, c: u8, kind: ErrorKind) -> Result<(), CompileError> {
+        if self.tk == c as i64 {
+            self.next();
+            Ok(())
+        } else {
+            Err(self.error(kind))
+        }
+    }
+
+    // Number of stack slots (or data words) a variable of type `ty` takes
+    fn slots(&self, ty: Ty) -> i64 {
+        (self.types.size(ty) + WORD_SIZE - 1) / WORD_SIZE
+    }
+
+    // Returns true if the current token starts a type: a type keyword or a typedef name
+    fn is_type(&self) -> bool {
+        matches!(self.tk, Int | Char | Short | Long | Unsigned | Signed | Float | Double | Void | Enum | Struct | Union) || (self.tk == Id && self.sym[self.id].class == Typedef)
+    }
+
+    // Parses a type specifier (void, an arithmetic type, enum, struct, union or a
+    // typedef name), or returns None if the current token does not start one
+    fn base_type(&mut self) -> Result<Option<Ty>, CompileError> {
+        if matches!(self.tk, Int | Char | Short | Long | Unsigned | Signed | Float | Double) {
+            self.arithmetic_type().map(Some)
+        } else if self.tk == Void {
+            self.next();
+            Ok(Some(VOID))
+        } else if self.tk == Enum {
+            self.enum_type()?;
+            Ok(Some(INT))
+        } else if self.tk == Struct || self.tk == Union {
+            self.record_type().map(Some)
+        } else if self.tk == Id && self.sym[self.id].class == Typedef {
+            let ty = self.sym[self.id].ty;
+            self.next();
+            Ok(Some(ty))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Parses the keywords naming an arithmetic type, in any order: signed or unsigned,
+    // and char, short, int, long or long long, where int may go with short or long and
+    // is implied by signed or unsigned alone; or float, double or long double
+    fn arithmetic_type(&mut self) -> Result<Ty, CompileError> {
+        let (mut sign, mut size, mut int, mut longs, mut real) = (None, None, false, 0, None);
+        loop {
+            match self.tk {
+                Signed | Unsigned if sign.is_none() && real.is_none() => sign = Some(self.tk),
+                Int if !int && size != Some(Char) && real.is_none() => int = true,
+                Char if size.is_none() && !int && real.is_none() => size = Some(Char),
+                Short if size.is_none() && real.is_none() => size = Some(Short),
+                Long if (size.is_none() || size == Some(Long)) && longs < 2 && (real.is_none() || real == Some(Double) && longs == 0) => {
+                    size = Some(Long);
+                    longs += 1;
+                }
+                Float if real.is_none() && sign.is_none() && size.is_none() && !int => real = Some(Float),
+                Double if real.is_none() && sign.is_none() && !int && (size.is_none() || size == Some(Long) && longs == 1) => real = Some(Double),
+                Int | Char | Short | Long | Signed | Unsigned | Float | Double => {
+                    return Err(self.error(ErrorKind::BadTypeSpecifiers));
+                }
+                _ => break,
+            }
+            self.next();
+        }
+        match real {
+            Some(Float) => return Ok(FLOAT),
+            Some(_) => return Ok(DOUBLE),
+            None => {}
+        }
+        let unsigned = sign == Some(Unsigned);
+        Ok(match size {
+            Some(Char) if unsigned => UCHAR,
+            Some(Char) => CHAR,
+            Some(Short) if unsigned => USHORT,
+            Some(Short) => SHORT,
+            Some(_) if unsigned => ULONG,
+            Some(_) => LONG,
+            None if unsigned => UINT,
+            None => INT,
+        })
+    }
+
+    // Parses an initializer for an object of type `ty` at byte offset `off`, returning
+    // the type with the length of an array of unknown size filled in
+    fn initializer(&mut self, ty: Ty, off: i64, target: &mut InitTarget) -> Result<Ty, CompileError> {
+        let aggregate = self.types.is_record(ty) || self.types.is_array(ty);
+        if self.tk == b'"' as i64 && self.types.deref(ty) == Some(CHAR) && self.types.is_array(ty) {
+            return self.string_initializer(ty, off, target);
+        }
+        if self.tk == b'{' as i64 {
+            self.next();
+            let result = self.brace_list(ty, aggregate, off, target);
+            if result.is_err() {
+                // Skip the rest of the list so that error recovery resumes after it
+                let mut depth = 0;
+                while self.tk != 0 && (depth > 0 || self.tk != b'}' as i64) {
+                    if self.tk == b'{' as i64 {
+                        depth += 1;
+                    } else if self.tk == b'}' as i64 {
+                        depth -= 1;
+                    }
+                    self.next();
+                }
+                self.next();
+            }
+            return result;
+        }
+        if aggregate {
+            return Err(self.error(ErrorKind::BadInitializer));
+        }
+        self.scalar_initializer(ty, off, target)?;
+        Ok(ty)
+    }
+
+    // Parses the contents of a brace-enclosed initializer, up to and including its '}'
+    fn brace_list(&mut self, ty: Ty, aggregate: bool, off: i64, target: &mut InitTarget) -> Result<Ty, CompileError> {
+        let ty = if aggregate {
+            self.members(ty, off, target)?
+        } else {
+            self.scalar_initializer(ty, off, target)?;
+            ty
+        };
+        if self.tk == b',' as i64 {
+            self.next();
+        }
+        self.expect(b'}', ErrorKind::ExcessInitializers)?;
+        Ok(ty)
+    }
+
+    // Initializes the elements of an array or the members of a struct from a brace
+    // list; the braces around a nested array or struct may be left out, in which case
+    // it takes as many values as it needs from the same list
+    fn members(&mut self, ty: Ty, off: i64, target: &mut InitTarget) -> Result<Ty, CompileError> {
+        let mut k = 0;
+        loop {
+            // Next element, or next member (only the first one for a union)
+            let (t, o) = match self.types.kind(ty) {
+                TypeKind::Array(elem, n) if n == 0 || k < n => (elem, off + k * self.types.size(elem)),
+                TypeKind::Record(_) => {
+                    let record = self.types.record(ty).unwrap();
+                    match record.fields.get(k as usize) {
+                        Some(f) if !record.union || k == 0 => (f.ty, off + f.offset),
+                        _ => break,
+                    }
+                }
+                _ => break,
+            };
+            if k > 0 {
+                if self.tk != b',' as i64 {
+                    break;
+                }
+                self.next();
+            }
+            if self.tk == b'}' as i64 {
+                break;
+            }
+            let nested = self.types.is_record(t) || self.types.is_array(t);
+            let string = self.tk == b'"' as i64 && self.types.is_array(t) && self.types.deref(t) == Some(CHAR);
+            if nested && self.tk != b'{' as i64 && !string {
+                self.members(t, o, target)?;
+            } else {
+                self.initializer(t, o, target)?;
+            }
+            k += 1;
+        }
+        match self.types.kind(ty) {
+            TypeKind::Array(elem, 0) => Ok(self.types.array(elem, k.max(1))),
+            _ => Ok(ty),
+        }
+    }
+
+    // Initializes a char array from a string literal, including its terminating NUL if
+    // there is room for it
+    fn string_initializer(&mut self, ty: Ty, off: i64, target: &mut InitTarget) -> Result<Ty, CompileError> {
+        // The literal was placed in the data segment by the lexer; take it back out
+        let start = (self.ival - self.data_addr(0)) as usize;
+        while self.tk == b'"' as i64 {
+            self.next();
+        }
+        let mut bytes = Vec::with_capacity(self.dp - start + 1);
+        for at in start..self.dp {
+            bytes.push((self.data[at / WORD_SIZE as usize] >> (at % WORD_SIZE as usize * 8)) as u8);
+            self.set_data_byte(at, 0);
+        }
+        self.dp = start;
+        bytes.push(0);
+        let ty = match self.types.kind(ty) {
+            TypeKind::Array(_, 0) => self.types.array(CHAR, bytes.len() as i64),
+            TypeKind::Array(_, n) if bytes.len() as i64 - 1 > n => {
+                return Err(self.error(ErrorKind::ExcessInitializers));
+            }
+            TypeKind::Array(_, n) => {
+                bytes.truncate(n as usize);
+                ty
+            }
+            _ => ty,
+        };
+        for (k, b) in bytes.into_iter().enumerate() {
+            self.init_value(CHAR, off + k as i64, b as i8 as i64, target);
+        }
+        Ok(ty)
+    }
+
+    // Initializes a scalar from an expression, which must be constant for a global
+    fn scalar_initializer(&mut self, ty: Ty, off: i64, target: &mut InitTarget) -> Result<(), CompileError> {
+        match target {
+            InitTarget::Data(_, forward) => {
+                let (v, f) = self.address_constant(Some(ty))?;
+                if let Some((f, at)) = f {
+                    forward.push((off as usize, f, at));
+                }
+                self.init_value(ty, off, v, target);
+            }
+            InitTarget::Frame(sites) => {
+                self.emit_member_addr(off, sites);
+                self.emit(PSH);
+                let at = self.span();
+                self.value(Assign)?;
+                if self.types.is_record(self.ty) {
+                    return Err(CompileError::new(ErrorKind::AggregateByValue, at));
+                }
+                self.emit_convert(ty);
+                self.emit_store();
+            }
+        }
+        Ok(())
+    }
+
+    // Initializes a scalar with a known value
+    fn init_value(&mut self, ty: Ty, off: i64, v: i64, target: &mut InitTarget) {
+        match target {
+            InitTarget::Data(values, _) => values.push((off as usize, ty, v)),
+            InitTarget::Frame(sites) => {
+                self.emit_member_addr(off, sites);
+                self.emit(PSH);
+                self.emit(IMM);
+                self.emit(v);
+                self.ty = ty;
+                self.emit_store();
+            }
+        }
+    }
+
+    // Emits the address `off` bytes into the local being initialized; the LEA operand
+    // is filled in once the local has its slots
+    fn emit_member_addr(&mut self, off: i64, sites: &mut Vec<usize>) {
+        self.emit(LEA);
+        self.emit(0);
+        sites.push(self.e);
+        if off != 0 {
+            self.emit(PSH);
+            self.emit(IMM);
+            self.emit(off);
+            self.emit(ADD);
+        }
+    }
+
+    // Emits a memset of the local being initialized to zero, returning the operands of
+    // its LEA and of its size
+    fn emit_clear(&mut self) -> (usize, usize) {
+        self.emit(LEA);
+        self.emit(0);
+        let addr = self.e;
+        self.emit(PSH);
+        self.emit(IMM);
+        self.emit(0);
+        self.emit(PSH);
+        self.emit(IMM);
+        self.emit(0);
+        let size = self.e;
+        self.emit(PSH);
+        self.emit(MSET);
+        self.emit(ADJ);
+        self.emit(3);
+        (addr, size)
+    }
+
+    // Parses a type name as used in casts and sizeof: a type specifier followed by an
+    // abstract declarator
+    fn type_name(&mut self) -> Result<Ty, CompileError> {
+        let ty = self.base_type()?.unwrap_or(INT);
+        let (ty, _, _) = self.declarator(ty, DeclKind::Local)?;
+        Ok(ty)
+    }
+
+    // Parses a declarator for base type `ty`: its '*'s, then its name (left out in type
+    // names and unnamed parameters) or a parenthesized declarator, then any array
+    // dimensions and parameter lists. Returns the declared type, and the name if there
+    // is one, with its location (otherwise that of the declarator).
+    fn declarator(&mut self, ty: Ty, kind: DeclKind) -> Result<(Ty, Option<usize>, Span), CompileError> {
+        let d = self.function_declarator(ty, kind)?;
+        Ok((d.ty, d.name, d.span))
+    }
+
+    // Parses a declarator like declarator(), also returning the parameters, with their
+    // names, of the parameter list directly after the name: those of the function the
+    // declarator declares, if its type is a function
+    fn function_declarator(&mut self, ty: Ty, kind: DeclKind) -> Result<Declarator, CompileError> {
+        let mut steps = Vec::new();
+        let mut name = None;
+        let mut at = self.span();
+        let mut params = None;
+        self.derivations(&mut steps, &mut name, &mut at, &mut params)?;
+        let mut ty = ty;
+        for step in steps {
+            ty = match step {
+                Derive::Ptr => self.types.ptr(ty),
+                Derive::Array(n, at) => {
+                    if !self.types.is_complete(ty) {
+                        return Err(CompileError::new(ErrorKind::IncompleteType, at));
+                    }
+                    if !self.types.fits(ty, n) {
+                        return Err(CompileError::new(ErrorKind::TypeTooLarge, at));
+                    }
+                    self.types.array(ty, n)
+                }
+                Derive::Func(params, variadic, at) => {
+                    // Functions can return neither arrays nor functions
+                    if self.types.is_array(ty) || self.types.is_func(ty) {
+                        return Err(CompileError::new(ErrorKind::BadDeclaration(kind), at));
+                    }
+                    if self.types.is_record(ty) {
+                        return Err(CompileError::new(ErrorKind::AggregateByValue, at));
+                    }
+                    self.types.func(ty, params, variadic)
+                }
+            };
+        }
+        Ok(Declarator { ty, name, span: at, params })
+    }
+
+    // Parses the steps of a declarator (see declarator()), appending them in the order
+    // they apply to the base type: the '*'s before the name, then the suffixes after it
+    // from the innermost outwards, then the steps of a parenthesized declarator, which
+    // binds loosest. Only the first array dimension may be left empty, giving an array
+    // of unknown size.
+    fn derivations(&mut self, steps: &mut Vec<Derive>, name: &mut Option<usize>, at: &mut Span, own: &mut Option<Vec<Param>>) -> Result<(), CompileError> {
+        let mut ptrs = 0;
+        while self.tk == Mul {
+            self.next();
+            ptrs += 1;
+        }
+        let mut inner = Vec::new();
+        let mut named = false;
+        if self.tk == b'(' as i64 && self.peek() == b'*' {
+            self.next();
+            self.derivations(&mut inner, name, at, own)?;
+            self.expect(b')', ErrorKind::MissingCloseParen(None))?;
+        } else if self.tk == Id {
+            (*name, *at) = (Some(self.id), self.span());
+            self.next();
+            named = true;
+        }
+        let mut suffixes = Vec::new();
+        loop {
+            let at = self.span();
+            if self.tk == Brak {
+                self.next();
+                let n = if self.tk == b']' as i64 && suffixes.is_empty() {
+                    0
+                } else {
+                    let size = self.span();
+                    let n = self.constant_expr()?;
+                    if n <= 0 {
+                        return Err(CompileError::new(ErrorKind::BadArraySize, size));
+                    }
+                    n
+                };
+                self.expect(b']', ErrorKind::MissingCloseBracket)?;
+                suffixes.push(Derive::Array(n, at));
+            } else if self.tk == b'(' as i64 {
+                self.next();
+                let prototyped = self.tk != b')' as i64;
+                let (params, variadic) = self.parameters()?;
+                let types = prototyped.then(|| params.iter().map(|p| p.ty).collect());
+                if named && suffixes.is_empty() {
+                    *own = Some(params);
+                }
+                suffixes.push(Derive::Func(types, variadic, at));
+            } else {
+                break;
+            }
+        }
+        steps.extend((0..ptrs).map(|_| Derive::Ptr));
+        steps.extend(suffixes.into_iter().rev());
+        steps.append(&mut inner);
+        Ok(())
+    }
+
+    // Parses an enum specifier, defining its enumerators as constants
+    fn enum_type(&mut self) -> Result<(), CompileError> {
+        self.next(); // Consume 'enum'
+        if self.tk != b'{' as i64 {
+            self.next(); // Skip enum name
+        }
+        if self.tk == b'{' as i64 {
+            self.next();
+            let mut i = 0; // Enum value counter
+            while self.tk != b'}' as i64 {
+                if self.tk != Id {
+                    return Err(self.error(ErrorKind::BadEnumIdentifier));
+                }
+                let (e, at) = (self.id, self.span());
+                self.next();
+                if self.tk == Assign {
+                    self.next();
+                    if self.tk != Num {
+                        return Err(self.error(ErrorKind::BadEnumInitializer));
+                    }
+                    i = self.ival; // Set enum value
+                    self.next();
+                }
+                self.shadow(e);
+                let s = &mut self.sym[e];
+                s.class = Num; // Mark as constant
+                s.ty = INT;
+                s.val = i; // Store value
+                s.span = Some(at);
+                i += 1;
+                if self.tk == b',' as i64 {
+                    self.next();
+                }
+            }
+            self.next(); // Consume '}'
+        }
+        Ok(())
+    }
+
+    // Parses a struct or union specifier: a reference to a tag, or a member list that
+    // defines the layout
+    fn record_type(&mut self) -> Result<Ty, CompileError> {
+        let union = self.tk == Union;
+        self.next(); // Consume 'struct' or 'union'
+        let mut tag = None;
+        if self.tk == Id {
+            tag = Some((self.id, self.span()));
+            self.next();
+        }
+        // Innermost tag of that name in scope
+        let known = tag.and_then(|(id, _)| self.tags.iter().rposition(|(t, _, _)| *t == id));
+        if self.tk != b'{' as i64 {
+            // Reference to a tag, declaring it if it is new
+            let Some((id, at)) = tag else {
+                return Err(self.error(ErrorKind::BadDeclaration(DeclKind::Member)));
+            };
+            return Ok(match known {
+                Some(k) => self.tags[k].1,
+                None => {
+                    let ty = self.types.new_record(union, Some(id));
+                    self.tags.push((id, ty, at));
+                    ty
+                }
+            });
+        }
+
+        // Definition: complete a tag forward-declared in the same block, or start a new
+        // one that hides any tag of the same name from enclosing blocks
+        let known = known.filter(|k| *k >= self.tag_scope);
+        let ty = match known {
+            Some(k) if !self.types.is_complete(self.tags[k].1) => {
+                if let Some((_, at)) = tag {
+                    self.tags[k].2 = at;
+                }
+                self.tags[k].1
+            }
+            Some(k) => {
+                let at = tag.map_or(self.span(), |(_, at)| at);
+                let err = CompileError::new(ErrorKind::DuplicateDefinition(DeclKind::Struct), at);
+                return Err(err.with_note("previous definition was here", self.tags[k].2));
+            }
+            None => {
+                let ty = self.types.new_record(union, tag.map(|(id, _)| id));
+                if let Some((id, at)) = tag {
+                    self.tags.push((id, ty, at));
+                }
+                ty
+            }
+        };
+        let open = self.span();
+        self.next(); // Consume '{'
+        let mut members: Vec<(usize, Ty)> = Vec::new();
+        while self.tk != b'}' as i64 {
+            if self.tk == 0 {
+                return Err(self.error(ErrorKind::MissingCloseBrace));
+            }
+            let Some(bt) = self.base_type()? else {
+                return Err(self.error(ErrorKind::BadDeclaration(DeclKind::Member)));
+            };
+            while self.tk != b';' as i64 {
+                let (mt, name, at) = self.declarator(bt, DeclKind::Member)?;
+                let Some(name) = name else {
+                    return Err(CompileError::new(ErrorKind::BadDeclaration(DeclKind::Member), at));
+                };
+                if members.iter().any(|(m, _)| *m == name) {
+                    return Err(CompileError::new(ErrorKind::DuplicateDefinition(DeclKind::Member), at));
+                }
+                if !self.types.is_complete(mt) {
+                    return Err(CompileError::new(ErrorKind::IncompleteType, at));
+                }
+                members.push((name, mt));
+                if self.tk == b',' as i64 {
+                    self.next();
+                } else if self.tk != b';' as i64 {
+                    return Err(self.error(ErrorKind::MissingSemicolon));
+                }
+            }
+            self.next(); // Consume ';'
+        }
+        self.next(); // Consume '}'
+        if !self.types.complete_record(ty, members) {
+            return Err(CompileError::new(ErrorKind::TypeTooLarge, tag.map_or(open, |(_, at)| at)));
+        }
+        Ok(ty)
+    }
+
+    // Marks the current identifier as a local, saving its outer meaning for restoration
+    fn declare_local(&mut self, id: usize, span: Span, ty: Ty, val: i64) {
+        self.shadow(id);
+        let s = &mut self.sym[id];
+        s.span = Some(span);
+        s.class = Loc;
+        s.ty = ty;
+        s.val = val;
+    }
+
+    // Saves the current meaning of symbol `id` before a declaration in a block gives
+    // it a new one; closing the block brings the saved meaning back
+    fn shadow(&mut self, id: usize) {
+        if self.depth == 0 {
+            return;
+        }
+        let s = &self.sym[id];
+        self.shadowed.push(Shadowed { id, class: s.class, ty: s.ty, val: s.val, span: s.span });
+    }
+
+    // Returns true if symbol `id` was declared in the current block
+    fn in_scope(&self, id: usize) -> bool {
+        self.shadowed[self.scope..].iter().any(|s| s.id == id)
+    }
+
+    // Opens a block scope
+    fn open_scope(&mut self) -> Scope {
+        let scope = Scope {
+            start: self.shadowed.len(),
+            outer: self.scope,
+            slot: self.slot,
+            tags: self.tags.len(),
+            outer_tags: self.tag_scope,
+        };
+        self.scope = self.shadowed.len();
+        self.tag_scope = self.tags.len();
+        self.depth += 1;
+        scope
+    }
+
+    // Closes a block scope: the names declared in it get their outer meaning back, and
+    // its stack slots are free for the blocks that follow
+    fn close_scope(&mut self, scope: Scope) {
+        while self.shadowed.len() > scope.start {
+            let Some(saved) = self.shadowed.pop() else { break };
+            let s = &mut self.sym[saved.id];
+            s.class = saved.class;
+            s.ty = saved.ty;
+            s.val = saved.val;
+            s.span = saved.span;
+        }
+        self.scope = scope.outer;
+        self.slot = scope.slot;
+        self.tags.truncate(scope.tags);
+        self.tag_scope = scope.outer_tags;
+        self.depth -= 1;
+    }
+
+    // Records a diagnostic; once the error cap is reached, skips to the end of input
+    fn report(&mut self, err: CompileError) {
+        if self.full {
+            return;
+        }
+        if self.errors.len() < self.max_errors {
+            self.errors.push(err);
+        }
+        if self.errors.len() >= self.max_errors {
+            self.p = self.src.len() - 1;
+            self.tk = 0;
+        }
+    }
+
+    // Skips the rest of a bad statement: up to and including ';', up to (not including)
+    // the '}' of the enclosing block, or past a nested block
+    fn sync_stmt(&mut self) {
+        let mut depth = 0;
+        while self.tk != 0 {
+            if self.tk == b';' as i64 && depth == 0 {
+                self.next();
+                return;
+            } else if self.tk == b'{' as i64 {
+                depth += 1;
+            } else if self.tk == b'}' as i64 {
+                if depth == 0 {
+                    return;
+                }
+                depth -= 1;
+                if depth == 0 {
+                    self.next();
+                    return;
+                }
+            }
+            self.next();
+        }
+    }
+
+    // Skips the rest of a bad top-level declaration: up to and including its ';' or
+    // closing '}', or up to the token that starts the next declaration
+    fn sync_decl(&mut self) {
+        let (mut braces, mut parens) = (0, 0);
+        while self.tk != 0 {
+            if braces == 0 && parens == 0 && (self.is_type() || self.tk == Typedef) {
+                return;
+            } else if self.tk == b';' as i64 && braces == 0 {
+                self.next();
+                return;
+            } else if self.tk == b'(' as i64 {
+                parens += 1;
+            } else if self.tk == b')' as i64 && parens > 0 {
+                parens -= 1;
+            } else if self.tk == b'{' as i64 {
+                braces += 1;
+            } else if self.tk == b'}' as i64 {
+                braces -= 1;
+                if braces <= 0 {
+                    self.next();
+                    return;
+                }
+            }
+            self.next();
+        }
+    }
+
+    // Parses declarations and statements up to the closing '}' of a block, recovering
+    // from errors in each; the '}' itself is left for the caller
+    fn block_items(&mut self) {
+        while self.tk != b'}' as i64 {
+            if self.tk == 0 {
+                let err = self.error(ErrorKind::MissingCloseBrace);
+                self.report(err);
+                return;
+            }
+            let result = if self.tk == Typedef {
+                self.typedef_declaration()
+            } else if self.is_type() {
+                self.local_declaration()
+            } else {
+                self.stmt()
+            };
+            if let Err(err) = result {
+                self.report(err);
+                self.sync_stmt();
+            }
+        }
+    }
+
+    // Adds a directory to the #include search path (-I)
+    pub fn add_include_path(&mut self, dir: impl Into<PathBuf>) {
+        self.pp.include_paths.push(dir.into());
+    }
+
+    // Defines an object-like macro before compiling (-D)
+    pub fn define(&mut self, name: &str, value: &str) {
+        self.pp.define(name, value);
+    }
+
+    // Files read while compiling; `Span::file` of every error indexes into this
+    pub fn files(&self) -> &[SourceFile] {
+        self.pp.files()
+    }
+
+    // Warnings reported by the last compile, whether or not it succeeded
+    pub fn warnings(&self) -> &[CompileError] {
+        &self.warnings
+    }
+
+    // Compiles a translation unit given as a string; #include "..." is resolved
+    // relative to the current directory
+    pub fn compile(&mut self, source: &str) -> Result<Program, Vec<CompileError>> {
+        self.compile_file("<input>", source)
+    }
+
+    // Compiles a whole translation unit read from the named file into a program, or
+    // returns every error found. Each call starts from a fresh symbol table and fresh
+    // code and data segments, keeping only the settings, the include path and the
+    // macros predefined with `define`, so a compiler can be used for any number of
+    // translation units.
+    pub fn compile_file(&mut self, name: &str, source: &str) -> Result<Program, Vec<CompileError>> {
+        let pp = std::mem::take(&mut self.pp);
+        *self = Compiler { listing: self.listing, max_errors: self.max_errors, pp, ..Compiler::new() };
+        let source = match self.pp.run(name, source) {
+            Ok((text, origins)) => {
+                self.origins = origins;
+                text
+            }
+            Err(mut errors) => {
+                errors.truncate(self.max_errors);
+                return Err(errors);
+            }
+        };
+        self.set_source(&source);
+        // Initialize parser
+        self.line = 1;
+        self.next(); // Get first token
+
+        // Parse global declarations
+        while self.tk != 0 {
+            if let Err(err) = self.declaration() {
+                self.report(err);
+                self.sync_decl();
+            }
+        }
+
+        // Every function called must have been defined
+        let mut calls = std::mem::take(&mut self.calls);
+        calls.sort_by_key(|&(f, site, _)| (f, site));
+        calls.dedup_by_key(|&mut (f, _, _)| f);
+        calls.sort_by_key(|&(_, site, _)| site);
+        // Then those whose address is only taken by global initializers
+        let mut taken = std::mem::take(&mut self.data_calls);
+        taken.retain(|&(f, _, _)| !calls.iter().any(|c| c.0 == f));
+        taken.sort_by_key(|&(f, off, _)| (f, off));
+        taken.dedup_by_key(|&mut (f, _, _)| f);
+        taken.sort_by_key(|&(_, off, _)| off);
+        calls.append(&mut taken);
+        for (f, _, at) in calls {
+            let name = self.sym[f].name.clone();
+            self.report(CompileError::new(ErrorKind::UndefinedFunction(name), at));
+        }
+
+        // Locate main function
+        if self.sym[self.idmain].class != Fun || self.sym[self.idmain].val == 0 {
+            let err = self.error(ErrorKind::MainNotDefined);
+            self.report(err);
+        }
+        let mut warnings = std::mem::take(&mut self.warnings);
+        self.locate_all(&mut warnings);
+        self.warnings = warnings;
+        if !self.errors.is_empty() {
+            let mut errors = std::mem::take(&mut self.errors);
+            self.locate_all(&mut errors);
+            return Err(errors);
+        }
+        Ok(Program {
+            main: self.sym[self.idmain].val as usize,
+            exit: 1,
+            code: std::mem::take(&mut self.code),
+            data: std::mem::take(&mut self.data),
+        })
+    }
+
+    // Maps the locations of errors and their notes back to the original source
+    fn locate_all(&self, errors: &mut [CompileError]) {
+        for err in errors.iter_mut() {
+            err.span = self.locate(err.span);
+            for note in err.notes.iter_mut() {
+                note.span = self.locate(note.span);
+            }
+        }
+    }
+
+    // Maps a span in the preprocessed source back to the file, line and column it came
+    // from; a span inside a macro expansion points at the macro's invocation
+    fn locate(&self, span: Span) -> Span {
+        match self.origins.get(span.line.wrapping_sub(1)) {
+            Some(o) => {
+                let column = o.column(span.column);
+                let start = o.offset + column - 1;
+                Span { start, end: start + (span.end - span.start), line: o.line, column, file: o.file }
+            }
+            None => span,
+        }
+    }
+
+    // Parses one top-level declaration, up to and including its ';' or closing '}'
+    fn declaration(&mut self) -> Result<(), CompileError> {
+        if self.tk == Typedef {
+            return self.typedef_declaration();
+        }
+        let bt = self.base_type()?.unwrap_or(INT); // Base type, int by default
+        // Parse variables or functions
+        while self.tk != b';' as i64 && self.tk != b'}' as i64 {
+            // The parameters of a function are in scope in its body
+            let scope = self.open_scope();
+            let Declarator { ty, name, span: at, params } = match self.function_declarator(bt, DeclKind::Global) {
+                Ok(d) => d,
+                Err(err) => {
+                    self.close_scope(scope);
+                    return Err(err);
+                }
+            };
+            // Anything but a function is a variable, function pointers included, as in
+            // `int (*f)(int)`
+            let Some(sig) = self.types.signature(ty).cloned() else {
+                self.close_scope(scope);
+                self.global_variable(ty, name, at)?;
+                if self.tk == b',' as i64 {
+                    self.next();
+                }
+                continue;
+            };
+            let Some(f) = name else {
+                self.close_scope(scope);
+                return Err(CompileError::new(ErrorKind::BadDeclaration(DeclKind::Global), at));
+            };
+            // Prototype of a function built into the virtual machine, as in the stub headers;
+            // calls are checked against it like calls to any other function
+            if self.sym[f].class == Sys {
+                self.close_scope(scope);
+                let s = &mut self.sym[f];
+                s.ty = sig.ret;
+                if sig.params.is_some() {
+                    s.params = sig.params;
+                    s.variadic = sig.variadic;
+                }
+                if self.tk == b',' as i64 {
+                    self.next();
+                }
+                continue;
+            }
+            // A function may be declared any number of times before it is defined
+            if self.sym[f].class != 0 && self.sym[f].class != Fun {
+                self.close_scope(scope);
+                return Err(self.redefinition(DeclKind::Global, f, at));
+            }
+            // A function type named through a typedef comes without parameter names
+            let params = params.unwrap_or_else(|| {
+                let types = sig.params.clone().unwrap_or_default();
+                types.into_iter().map(|ty| Param { ty, name: None, span: at }).collect()
+            });
+            let result = self.function(f, sig, params, at);
+            self.close_scope(scope);
+            if result? {
+                break; // A function body ends the declaration
+            }
+            if self.tk == b',' as i64 {
+                self.next();
+            }
+        }
+        self.next(); // Consume ';' or '}'
+        Ok(())
+    }
+
+    // Defines a global variable of type `ty` from its declarator, and parses its initializer
+    fn global_variable(&mut self, mut ty: Ty, g: Option<usize>, at: Span) -> Result<(), CompileError> {
+        let Some(g) = g else {
+            return Err(CompileError::new(ErrorKind::BadDeclaration(DeclKind::Global), at));
+        };
+        if self.sym[g].class != 0 {
+            return Err(self.redefinition(DeclKind::Global, g, at));
+        }
+        self.sym[g].span = Some(at);
+        // An array of unknown size gets its space once the initializer has given its length
+        let complete = self.types.is_complete(ty);
+        if complete {
+            self.define_global(g, ty);
+        }
+        if self.tk == Assign {
+            self.next();
+            let mut target = InitTarget::Data(Vec::new(), Vec::new());
+            ty = self.initializer(ty, 0, &mut target)?;
+            if !complete && self.types.is_complete(ty) {
+                self.define_global(g, ty);
+            }
+            // Write the values straight into the data segment
+            let InitTarget::Data(values, forward) = target else { unreachable!() };
+            let base = (self.sym[g].val - self.data_addr(0)) as usize;
+            for (off, t, v) in values {
+                let v = if t == FLOAT { (float(v) as f32).to_bits() as i64 } else { v };
+                for (k, b) in v.to_ne_bytes().into_iter().take(self.types.size(t) as usize).enumerate() {
+                    self.set_data_byte(base + off + k, b);
+                }
+            }
+            // Patched when the function is defined
+            for (off, f, at) in forward {
+                self.data_calls.push((f, base + off, at));
+            }
+        }
+        if !self.types.is_complete(ty) {
+            return Err(CompileError::new(ErrorKind::IncompleteType, at));
+        }
+        Ok(())
+    }
+
+    // Gives global `g` of type `ty` its space in the data segment
+    fn define_global(&mut self, g: usize, ty: Ty) {
+        let addr = self.data_addr(self.dp);
+        let s = &mut self.sym[g];
+        s.class = Glo;
+        s.ty = ty;
+        s.val = addr; // Data segment address
+        self.alloc_data(self.slots(ty) as usize * WORD_SIZE as usize); // Allocate space
+    }
+
+    // Declares function `f` with signature `sig` and, unless this is only a prototype,
+    // parses its body, with `params` in scope; the current token follows the declarator.
+    // Returns true if the function was defined.
+    fn function(&mut self, f: usize, sig: Signature, params: Vec<Param>, at: Span) -> Result<bool, CompileError> {
+        // The parameter types are None for an empty list `()`, which leaves calls unchecked
+        let Signature { ret: ty, params: types, variadic } = sig;
+        // Every declaration of a function must agree with the earlier ones
+        if self.sym[f].class == Fun {
+            let s = &self.sym[f];
+            let agree = self.types.params_agree(s.params.as_deref(), s.variadic, types.as_deref(), variadic);
+            if s.ty != ty || !agree {
+                let err = CompileError::new(ErrorKind::ConflictingTypes(s.name.clone()), at);
+                return Err(match s.span {
+                    Some(prev) => err.with_note("previous declaration was here", prev),
+                    None => err,
+                });
+            }
+        }
+        let defined = self.sym[f].class == Fun && self.sym[f].val != 0;
+        let s = &mut self.sym[f];
+        s.ty = ty;
+        if types.is_some() {
+            s.params = types;
+            s.variadic = variadic;
+        }
+        // Prototype: declared, with no address until the definition is seen
+        if self.tk != b'{' as i64 {
+            if self.tk != b';' as i64 && self.tk != b',' as i64 {
+                return Err(self.error(ErrorKind::BadFunctionDefinition));
+            }
+            if self.sym[f].class != Fun {
+                let s = &mut self.sym[f];
+                s.class = Fun;
+                s.val = 0;
+                s.span = Some(at);
+            }
+            return Ok(false);
+        }
+        if variadic {
+            return Err(CompileError::new(ErrorKind::VariadicDefinition, at));
+        }
+        if defined {
+            return Err(self.redefinition(DeclKind::Global, f, at));
+        }
+        let s = &mut self.sym[f];
+        s.class = Fun;
+        s.val = (self.e + 1) as i64; // Function address
+        s.span = Some(at);
+        // Patch the calls made before the definition
+        let addr = s.val;
+        for &(_, site, _) in self.calls.iter().filter(|c| c.0 == f) {
+            self.code[site] = addr;
+        }
+        self.calls.retain(|c| c.0 != f);
+        let sites: Vec<usize> = self.data_calls.iter().filter(|c| c.0 == f).map(|c| c.1).collect();
+        for off in sites {
+            for (k, b) in addr.to_ne_bytes().into_iter().enumerate() {
+                self.set_data_byte(off + k, b);
+            }
+        }
+        self.data_calls.retain(|c| c.0 != f);
+        // Declare the parameters; they share a scope with the outermost block of the body
+        let mut i = 0; // Parameter counter
+        let mut floats = Vec::new(); // Float parameters, passed as doubles
+        for Param { ty, name, span: at } in params {
+            let Some(p) = name else {
+                return Err(CompileError::new(ErrorKind::BadDeclaration(DeclKind::Parameter), at));
+            };
+            if self.in_scope(p) {
+                return Err(self.redefinition(DeclKind::Parameter, p, at));
+            }
+            self.declare_local(p, at, ty, i);
+            if ty == FLOAT {
+                floats.push(i);
+            }
+            i += 1;
+        }
+        i += 1;
+        self.loc = i; // Set local variable offset
+        self.ret = ty;
+        self.slot = i;
+        self.frame = i;
+        self.next(); // Consume '{'
+        // Emit function entry; the frame size is known once the whole body is parsed
+        self.emit(ENT);
+        self.emit(0);
+        let frame = self.e;
+        // Store float parameters as floats in their own slots
+        for p in floats {
+            self.emit(LEA);
+            self.emit(self.loc - p);
+            self.emit(PSH);
+            self.emit(LEA);
+            self.emit(self.loc - p);
+            self.emit(LI);
+            self.emit(SF);
+        }
+        // Parse function body
+        let errors = self.errors.len();
+        self.labels.clear();
+        self.block_items();
+        self.check_labels();
+        // Only main() may end without returning a value (as if it returned 0)
+        if self.ret != VOID && f != self.idmain && self.errors.len() == errors && self.falls_off_end(frame - 1) {
+            self.warnings.push(self.error(ErrorKind::MissingReturn));
+        }
+        self.code[frame] = self.frame - self.loc; // Stack frame size
+        // Emit function exit
+        self.emit(LEV);
+        Ok(true)
+    }
+
+    // Parses a parameter list up to and including its ')': the type of each parameter,
+    // its name if it has one, and its location, and whether the list ends with '...'
+    fn parameters(&mut self) -> Result<(Vec<Param>, bool), CompileError> {
+        let mut params = Vec::new();
+        let mut variadic = false;
+        // (void) declares that there are no parameters
+        if self.tk == Void && self.peek() == b')' {
+            self.next();
+        }
+        while self.tk != b')' as i64 {
+            // '...' after the last parameter
+            if self.tk == Dot && !params.is_empty() {
+                for _ in 0..3 {
+                    if self.tk != Dot {
+                        return Err(self.error(ErrorKind::BadDeclaration(DeclKind::Parameter)));
+                    }
+                    self.next();
+                }
+                variadic = true;
+                if self.tk != b')' as i64 {
+                    return Err(self.error(ErrorKind::MissingCloseParen(None)));
+                }
+                break;
+            }
+            let start = self.span();
+            let bt = self.base_type()?.unwrap_or(INT);
+            let (ty, name, at) = self.declarator(bt, DeclKind::Parameter)?;
+            let at = if name.is_some() { at } else { start };
+            // An array parameter is a pointer to its first element, and a function
+            // parameter a pointer to the function
+            let ty = self.types.decay(ty);
+            if self.types.is_record(ty) {
+                return Err(CompileError::new(ErrorKind::AggregateByValue, at));
+            } else if ty == VOID {
+                return Err(CompileError::new(ErrorKind::IncompleteType, at));
+            }
+            params.push(Param { ty, name, span: at });
+            if self.tk == b',' as i64 {
+                self.next();
+            } else if self.tk != b')' as i64 {
+                return Err(self.error(ErrorKind::BadDeclaration(DeclKind::Parameter)));
+            }
+        }
+        self.next(); // Consume ')'
+        Ok((params, variadic))
+    }
+
+    // Parses a typedef declaration, up to and including its ';'. Typedef names are
+    // symbols of class Typedef, scoped like variables; a typedef may be repeated with
+    // the same type.
+    fn typedef_declaration(&mut self) -> Result<(), CompileError> {
+        self.next(); // Consume 'typedef'
+        let Some(bt) = self.base_type()? else {
+            return Err(self.error(ErrorKind::BadDeclaration(DeclKind::Typedef)));
+        };
+        while self.tk != b';' as i64 {
+            let (ty, t, at) = self.declarator(bt, DeclKind::Typedef)?;
+            let Some(t) = t else {
+                return Err(CompileError::new(ErrorKind::BadDeclaration(DeclKind::Typedef), at));
+            };
+            let taken = if self.depth == 0 { self.sym[t].class != 0 } else { self.in_scope(t) };
+            if taken {
+                if self.sym[t].class != Typedef || self.sym[t].ty != ty {
+                    return Err(self.redefinition(DeclKind::Typedef, t, at));
+                }
+            } else {
+                self.shadow(t);
+                let s = &mut self.sym[t];
+                s.class = Typedef;
+                s.ty = ty;
+                s.val = 0;
+                s.span = Some(at);
+            }
+            if self.tk == b',' as i64 {
+                self.next();
+            } else if self.tk != b';' as i64 {
+                return Err(self.error(ErrorKind::MissingSemicolon));
+            }
+        }
+        self.next(); // Consume ';'
+        Ok(())
+    }
+
+    // Parses one line of local variable declarations, giving each its stack slots
+    fn local_declaration(&mut self) -> Result<(), CompileError> {
+        let bt = self.base_type()?.unwrap_or(INT);
+        while self.tk != b';' as i64 {
+            let (mut ty, l, at) = self.declarator(bt, DeclKind::Local)?;
+            let Some(l) = l else {
+                return Err(CompileError::new(ErrorKind::BadDeclaration(DeclKind::Local), at));
+            };
+            if self.in_scope(l) {
+                return Err(self.redefinition(DeclKind::Local, l, at));
+            }
+            // Save and set symbol attributes; a struct or array takes several slots and
+            // its address is that of the lowest one. An array of unknown size gets its
+            // slots once the initializer has given its length.
+            let complete = self.types.is_complete(ty);
+            if complete {
+                self.alloc_local(l, at, ty)?;
+            }
+            if self.tk == Assign {
+                self.next();
+                // Members and elements not initialized explicitly are zero
+                let aggregate = self.types.is_record(ty) || self.types.is_array(ty);
+                let clear = aggregate.then(|| self.emit_clear());
+                let mut target = InitTarget::Frame(Vec::new());
+                ty = self.initializer(ty, 0, &mut target)?;
+                if !complete && self.types.is_complete(ty) {
+                    self.alloc_local(l, at, ty)?;
+                }
+                // Point the stores at the local's slots
+                let InitTarget::Frame(mut sites) = target else { unreachable!() };
+                if let Some((addr, size)) = clear {
+                    sites.push(addr);
+                    self.code[size] = self.types.size(ty);
+                }
+                for site in sites {
+                    self.code[site] = self.loc - self.sym[l].val;
+                }
+            }
+            if !self.types.is_complete(ty) {
+                return Err(CompileError::new(ErrorKind::IncompleteType, at));
+            }
+            if self.tk == b',' as i64 {
+                self.next();
+            }
+        }
+        self.next(); // Consume ';'
+        Ok(())
+    }
+
+    // Declares local `id` of type `ty` in the next free stack slots, which must leave
+    // room on the stack for at least the frame itself
+    fn alloc_local(&mut self, id: usize, span: Span, ty: Ty) -> Result<(), CompileError> {
+        if self.slots(ty) > (POOL_SIZE as i64 / WORD_SIZE) - self.slot {
+            // Still declare it, so that its uses are not reported as undefined
+            self.declare_local(id, span, ty, self.slot);
+            return Err(CompileError::new(ErrorKind::FrameTooLarge, span));
+        }
+        self.slot += self.slots(ty);
+        self.frame = self.frame.max(self.slot);
+        self.declare_local(id, span, ty, self.slot);
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+// Rendering of compile errors: human-readable with source snippets, or JSON for tools
+
+use std::fmt::Write;
+
+use crate::error::{CompileError, Span};
+use crate::preprocess::SourceFile;
+
+// Renders an error and its notes, each followed by the source line it points at and
+// a `^~~~` marker under the offending token:
+//
+//     test.c:4:7: error: duplicate global definition
+//      4 | int g;
+//        |     ^
+//     test.c:2:5: note: previous definition was here
+//      2 | int g;
+//        |     ^
+//
+// `files` are the files the compiler read, as returned by `Compiler::files()`.
+pub fn render(err: &CompileError, files: &[SourceFile]) -> String {
+    let mut out = String::new();
+    render_one(&mut out, files, &err.kind.severity().to_string(), &err.kind.to_string(), err.span);
+    for note in &err.notes {
+        render_one(&mut out, files, "note", &note.message, note.span);
+    }
+    out
+}
+
+// Name of the file a span points into
+fn file_name(files: &[SourceFile], span: Span) -> &str {
+    files.get(span.file).map_or("<input>", |f| f.name.as_str())
+}
+
+// Renders one message header plus its snippet
+fn render_one(out: &mut String, files: &[SourceFile], severity: &str, message: &str, span: Span) {
+    let _ = writeln!(out, "{}:{}:{}: {}: {}", file_name(files, span), span.line, span.column, severity, message);
+    let source = files.get(span.file).map_or("", |f| f.text.as_str());
+    // Nothing to show for errors at the very end of the input
+    if span.start >= source.len() {
+        return;
+    }
+
+    // Find the line the span starts on
+    let line_start = span.start.saturating_sub(span.column.saturating_sub(1)).min(source.len());
+    let line_end = source[line_start..].find('\n').map_or(source.len(), |i| line_start + i);
+    let text = source.get(line_start..line_end).unwrap_or("");
+    let gutter = span.line.to_string();
+    let _ = writeln!(out, " {} | {}", gutter, text);
+
+    // Underline the span, keeping tabs so the marker lines up with the text above
+    let start = span.start.clamp(line_start, line_end);
+    let pad: String = source.get(line_start..start).unwrap_or("").chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+    let width = span.end.min(line_end).saturating_sub(start).max(1);
+    let _ = writeln!(out, " {} | {}^{}", " ".repeat(gutter.len()), pad, "~".repeat(width - 1));
+}
+
+// Renders an error as a single-line JSON object:
+//
+//     {"file":"test.c","line":4,"column":5,"severity":"error","code":"duplicate-definition",
+//      "message":"duplicate global definition","notes":[{"file":"test.c","line":2,"column":5,"message":"..."}]}
+pub fn render_json(err: &CompileError, files: &[SourceFile]) -> String {
+    let notes: Vec<String> = err
+        .notes
+        .iter()
+        .map(|n| {
+            format!(
+                "{{\"file\":{},\"line\":{},\"column\":{},\"message\":{}}}",
+                json_string(file_name(files, n.span)),
+                n.span.line,
+                n.span.column,
+                json_string(&n.message)
+            )
+        })
+        .collect();
+    json_object(file_name(files, err.span), err.span.line, err.span.column, &err.kind.severity().to_string(), err.kind.code(), &err.kind.to_string(), &notes.join(","))
+}
+
+// Renders a diagnostic that is not tied to a source location (e.g. an unreadable file)
+pub fn render_json_message(file: &str, code: &str, message: &str) -> String {
+    json_object(file, 0, 0, "error", code, message, "")
+}
+
+// Builds one diagnostic object; `notes` is the already-rendered contents of the notes array
+fn json_object(file: &str, line: usize, column: usize, severity: &str, code: &str, message: &str, notes: &str) -> String {
+    format!(
+        "{{\"file\":{},\"line\":{},\"column\":{},\"severity\":{},\"code\":{},\"message\":{},\"notes\":[{}]}}",
+        json_string(file),
+        line,
+        column,
+        json_string(severity),
+        json_string(code),
+        json_string(message),
+        notes
+    )
+}
+
+// Quotes and escapes a string for JSON
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,375 @@
+// Compile errors reported by the preprocessor and the parser
+
+use std::fmt;
+
+// Location of a token in the source: file, byte range plus 1-based line and column
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize, // Byte offset of the first character
+    pub end: usize, // Byte offset one past the last character
+    pub line: usize, // Line number
+    pub column: usize, // Column number, counted in bytes
+    pub file: usize, // Index into Compiler::files()
+}
+
+// How serious a diagnostic is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error, // Compilation fails
+    Warning, // Compilation goes on
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+// Which kind of declaration a declaration error refers to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeclKind {
+    Global, // File-scope variable or function
+    Parameter, // Function parameter
+    Local, // Local variable
+    Member, // Struct or union member
+    Struct, // Struct or union type
+    Typedef, // Typedef name
+}
+
+// Which construct a bad lvalue was found in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LvalueContext {
+    Assignment, // Left-hand side of '='
+    PreIncrement, // Operand of prefix '++'/'--'
+    PostIncrement, // Operand of postfix '++'/'--'
+}
+
+// Everything that can go wrong while compiling
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnexpectedEof, // Input ended in the middle of an expression
+    MissingOpenParen(Option<&'static str>), // '(' expected, optionally naming the construct
+    MissingCloseParen(Option<&'static str>), // ')' expected, optionally naming the construct
+    MissingCloseBracket, // ']' expected after a subscript
+    MissingSemicolon, // ';' expected after a statement
+    MissingColon, // ':' expected in a conditional expression
+    MissingCloseBrace, // Input ended inside a block
+    MissingWhile, // 'while' expected after the body of a 'do'
+    UnterminatedComment, // Input ended inside a /* comment
+    UnknownDirective(String), // '#' followed by something that is not a directive
+    UnmatchedDirective(&'static str), // #elif/#else/#endif out of place, with a description
+    UnterminatedConditional, // #if without #endif
+    BadIfExpression, // Malformed #if/#elif/#ifdef/#ifndef condition
+    BadMacroDefinition, // Malformed #define or #undef
+    BadMacroCall(String), // Function-like macro call without closing paren
+    MacroArgCount(String), // Function-like macro called with the wrong number of arguments
+    BadInclude, // #include without a file name
+    IncludeNotFound(String), // #include of a file that is not in any search path
+    IncludeTooDeep, // #include nested past the limit
+    ErrorDirective(String), // #error, with its message
+    BadEscape(String), // Unknown escape sequence in a literal, as written
+    BadLvalue(LvalueContext), // Expression cannot be assigned to
+    BadCast, // Malformed cast
+    BadDereference, // '*' applied to a non-pointer
+    BadAddressOf, // '&' applied to a non-lvalue
+    BadFunctionCall, // Call of something that is not a function
+    UndefinedVariable, // Use of an undeclared name
+    BadExpression, // Token cannot start an expression
+    PointerExpected, // Subscript of a non-pointer
+    BadEnumIdentifier, // Enumerator is not an identifier
+    BadEnumInitializer, // Enumerator value is not a number
+    BadDeclaration(DeclKind), // Declarator without a name
+    DuplicateDefinition(DeclKind), // Name defined twice in the same scope
+    BadFunctionDefinition, // Function header not followed by a body
+    BreakOutsideLoop, // 'break' not inside a loop or switch
+    ContinueOutsideLoop, // 'continue' not inside a loop
+    CaseOutsideSwitch, // 'case' label not inside a switch
+    DefaultOutsideSwitch, // 'default' label not inside a switch
+    DuplicateCase, // Two 'case' labels with the same value in one switch
+    SwitchNotInteger, // Controlling expression of a switch that is not an integer
+    DuplicateDefault, // Two 'default' labels in one switch
+    NotConstant, // Expression must be an integer constant
+    BadLabel, // 'goto' not followed by a label name
+    UndefinedLabel, // 'goto' to a label the function does not define
+    DuplicateLabel, // Label defined twice in one function
+    UnusedLabel, // Label no 'goto' refers to (warning)
+    BadArraySize, // Array dimension that is zero or negative
+    TypeTooLarge, // Array or struct whose size in bytes does not fit in a long
+    BadInitializer, // Array or struct initialized from something other than a brace list
+    ExcessInitializers, // More values in an initializer than the object has room for
+    IncompleteType, // Variable or sizeof of a struct whose members are not known
+    BadMemberAccess, // '.' or '->' not applied to a struct or union (or a pointer to one)
+    NoSuchMember(String), // Struct or union has no member of that name
+    AggregateByValue, // Struct or union assigned, passed or returned by value
+    UndeclaredFunction(String), // Call to a name with no declaration
+    ConflictingTypes(String), // Function declared again with a different type
+    VariadicDefinition, // Definition of a function taking '...'
+    TooFewArguments(Option<String>), // Call passing fewer arguments than the function has parameters
+    TooManyArguments(Option<String>), // Call passing more arguments than the function has parameters
+    IntToPointer(usize), // Integer passed for a pointer parameter (warning)
+    PointerToInt(usize), // Pointer passed for an integer parameter (warning)
+    IncompatiblePointer(usize), // Pointer to one type passed for a pointer to another (warning)
+    IncompatibleArgument(usize), // Floating-point value passed for a pointer parameter, or the reverse
+    UndefinedFunction(String), // Call to a function that is declared but never defined
+    BuiltinAddress(String), // Address taken of a function built into the virtual machine
+    BadTypeSpecifiers, // Type keywords that do not go together, such as 'short long'
+    BadOperands, // Operator applied to operands it does not take, such as '%' on a double
+    VoidValue, // Result of a void expression used as a value
+    ReturnValueInVoid, // 'return' with a value in a function returning void
+    ReturnWithoutValue, // 'return' with no value in a function returning a value (warning)
+    MissingReturn, // Function returning a value whose body can end without a 'return' (warning)
+    MainNotDefined, // No main() in the translation unit
+    ProgramTooLarge, // Generated code does not fit in the code segment
+    DataTooLarge, // Globals, string literals and jump tables do not fit in the data segment
+    FrameTooLarge, // Local variables of a function do not fit on the stack
+    Internal(i64), // Parser reached a token it has no rule for
+}
+
+impl fmt::Display for DeclKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DeclKind::Global => "global",
+            DeclKind::Parameter => "parameter",
+            DeclKind::Local => "local",
+            DeclKind::Member => "member",
+            DeclKind::Struct => "struct",
+            DeclKind::Typedef => "typedef",
+        })
+    }
+}
+
+impl ErrorKind {
+    // Whether the diagnostic stops compilation
+    pub fn severity(&self) -> Severity {
+        match self {
+            ErrorKind::UnusedLabel
+            | ErrorKind::IntToPointer(_)
+            | ErrorKind::PointerToInt(_)
+            | ErrorKind::IncompatiblePointer(_)
+            | ErrorKind::ReturnWithoutValue
+            | ErrorKind::MissingReturn => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    // Stable identifier for the kind of error, for tools that consume diagnostics
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::UnexpectedEof => "unexpected-eof",
+            ErrorKind::MissingOpenParen(_) => "missing-open-paren",
+            ErrorKind::MissingCloseParen(_) => "missing-close-paren",
+            ErrorKind::MissingCloseBracket => "missing-close-bracket",
+            ErrorKind::MissingSemicolon => "missing-semicolon",
+            ErrorKind::MissingColon => "missing-colon",
+            ErrorKind::MissingCloseBrace => "missing-close-brace",
+            ErrorKind::MissingWhile => "missing-while",
+            ErrorKind::UnterminatedComment => "unterminated-comment",
+            ErrorKind::UnknownDirective(_) => "unknown-directive",
+            ErrorKind::UnmatchedDirective(_) => "unmatched-directive",
+            ErrorKind::UnterminatedConditional => "unterminated-conditional",
+            ErrorKind::BadIfExpression => "bad-if-expression",
+            ErrorKind::BadMacroDefinition => "bad-macro-definition",
+            ErrorKind::BadMacroCall(_) => "bad-macro-call",
+            ErrorKind::MacroArgCount(_) => "macro-arg-count",
+            ErrorKind::BadInclude => "bad-include",
+            ErrorKind::IncludeNotFound(_) => "include-not-found",
+            ErrorKind::IncludeTooDeep => "include-too-deep",
+            ErrorKind::ErrorDirective(_) => "error-directive",
+            ErrorKind::BadEscape(_) => "bad-escape",
+            ErrorKind::BadLvalue(_) => "bad-lvalue",
+            ErrorKind::BadCast => "bad-cast",
+            ErrorKind::BadDereference => "bad-dereference",
+            ErrorKind::BadAddressOf => "bad-address-of",
+            ErrorKind::BadFunctionCall => "bad-function-call",
+            ErrorKind::UndefinedVariable => "undefined-variable",
+            ErrorKind::BadExpression => "bad-expression",
+            ErrorKind::PointerExpected => "pointer-expected",
+            ErrorKind::BadEnumIdentifier => "bad-enum-identifier",
+            ErrorKind::BadEnumInitializer => "bad-enum-initializer",
+            ErrorKind::BadDeclaration(_) => "bad-declaration",
+            ErrorKind::DuplicateDefinition(_) => "duplicate-definition",
+            ErrorKind::BadFunctionDefinition => "bad-function-definition",
+            ErrorKind::BreakOutsideLoop => "break-outside-loop",
+            ErrorKind::ContinueOutsideLoop => "continue-outside-loop",
+            ErrorKind::CaseOutsideSwitch => "case-outside-switch",
+            ErrorKind::DefaultOutsideSwitch => "default-outside-switch",
+            ErrorKind::DuplicateCase => "duplicate-case",
+            ErrorKind::SwitchNotInteger => "switch-not-integer",
+            ErrorKind::DuplicateDefault => "duplicate-default",
+            ErrorKind::NotConstant => "not-constant",
+            ErrorKind::BadLabel => "bad-label",
+            ErrorKind::UndefinedLabel => "undefined-label",
+            ErrorKind::DuplicateLabel => "duplicate-label",
+            ErrorKind::UnusedLabel => "unused-label",
+            ErrorKind::BadArraySize => "bad-array-size",
+            ErrorKind::TypeTooLarge => "type-too-large",
+            ErrorKind::BadInitializer => "bad-initializer",
+            ErrorKind::ExcessInitializers => "excess-initializers",
+            ErrorKind::IncompleteType => "incomplete-type",
+            ErrorKind::BadMemberAccess => "bad-member-access",
+            ErrorKind::NoSuchMember(_) => "no-such-member",
+            ErrorKind::AggregateByValue => "aggregate-by-value",
+            ErrorKind::UndeclaredFunction(_) => "undeclared-function",
+            ErrorKind::ConflictingTypes(_) => "conflicting-types",
+            ErrorKind::VariadicDefinition => "variadic-definition",
+            ErrorKind::TooFewArguments(_) => "too-few-arguments",
+            ErrorKind::TooManyArguments(_) => "too-many-arguments",
+            ErrorKind::IntToPointer(_) => "int-to-pointer",
+            ErrorKind::PointerToInt(_) => "pointer-to-int",
+            ErrorKind::IncompatiblePointer(_) => "incompatible-pointer",
+            ErrorKind::IncompatibleArgument(_) => "incompatible-argument",
+            ErrorKind::UndefinedFunction(_) => "undefined-function",
+            ErrorKind::BuiltinAddress(_) => "builtin-address",
+            ErrorKind::BadTypeSpecifiers => "bad-type-specifiers",
+            ErrorKind::BadOperands => "bad-operands",
+            ErrorKind::VoidValue => "void-value",
+            ErrorKind::ReturnValueInVoid => "return-value-in-void",
+            ErrorKind::ReturnWithoutValue => "return-without-value",
+            ErrorKind::MissingReturn => "missing-return",
+            ErrorKind::MainNotDefined => "main-not-defined",
+            ErrorKind::ProgramTooLarge => "program-too-large",
+            ErrorKind::DataTooLarge => "data-too-large",
+            ErrorKind::FrameTooLarge => "frame-too-large",
+            ErrorKind::Internal(_) => "internal",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::UnexpectedEof => write!(f, "unexpected eof in expression"),
+            ErrorKind::MissingOpenParen(None) => write!(f, "open paren expected"),
+            ErrorKind::MissingOpenParen(Some(what)) => write!(f, "open paren expected in {}", what),
+            ErrorKind::MissingCloseParen(None) => write!(f, "close paren expected"),
+            ErrorKind::MissingCloseParen(Some(what)) => write!(f, "close paren expected in {}", what),
+            ErrorKind::MissingCloseBracket => write!(f, "close bracket expected"),
+            ErrorKind::MissingSemicolon => write!(f, "semicolon expected"),
+            ErrorKind::MissingColon => write!(f, "conditional missing colon"),
+            ErrorKind::MissingCloseBrace => write!(f, "close brace expected"),
+            ErrorKind::MissingWhile => write!(f, "while expected after do body"),
+            ErrorKind::UnterminatedComment => write!(f, "unterminated comment"),
+            ErrorKind::UnknownDirective(name) => write!(f, "invalid preprocessing directive #{}", name),
+            ErrorKind::UnmatchedDirective(what) => write!(f, "{}", what),
+            ErrorKind::UnterminatedConditional => write!(f, "unterminated conditional directive"),
+            ErrorKind::BadIfExpression => write!(f, "bad #if expression"),
+            ErrorKind::BadMacroDefinition => write!(f, "bad macro definition"),
+            ErrorKind::BadMacroCall(name) => write!(f, "unterminated argument list invoking macro '{}'", name),
+            ErrorKind::MacroArgCount(name) => write!(f, "wrong number of arguments to macro '{}'", name),
+            ErrorKind::BadInclude => write!(f, "#include expects \"FILENAME\" or <FILENAME>"),
+            ErrorKind::IncludeNotFound(name) => write!(f, "'{}' file not found", name),
+            ErrorKind::IncludeTooDeep => write!(f, "#include nested too deeply"),
+            ErrorKind::ErrorDirective(msg) => write!(f, "#error {}", msg),
+            ErrorKind::BadEscape(text) => write!(f, "unknown escape sequence '{}'", text),
+            ErrorKind::BadLvalue(LvalueContext::Assignment) => write!(f, "bad lvalue in assignment"),
+            ErrorKind::BadLvalue(LvalueContext::PreIncrement) => write!(f, "bad lvalue in pre-increment"),
+            ErrorKind::BadLvalue(LvalueContext::PostIncrement) => write!(f, "bad lvalue in post-increment"),
+            ErrorKind::BadCast => write!(f, "bad cast"),
+            ErrorKind::BadDereference => write!(f, "bad dereference"),
+            ErrorKind::BadAddressOf => write!(f, "bad address-of"),
+            ErrorKind::BadFunctionCall => write!(f, "bad function call"),
+            ErrorKind::UndefinedVariable => write!(f, "undefined variable"),
+            ErrorKind::BadExpression => write!(f, "bad expression"),
+            ErrorKind::PointerExpected => write!(f, "pointer type expected"),
+            ErrorKind::BadEnumIdentifier => write!(f, "bad enum identifier"),
+            ErrorKind::BadEnumInitializer => write!(f, "bad enum initializer"),
+            ErrorKind::BadDeclaration(kind) => write!(f, "bad {} declaration", kind),
+            ErrorKind::DuplicateDefinition(kind) => write!(f, "duplicate {} definition", kind),
+            ErrorKind::BadFunctionDefinition => write!(f, "bad function definition"),
+            ErrorKind::BreakOutsideLoop => write!(f, "break statement not within loop or switch"),
+            ErrorKind::ContinueOutsideLoop => write!(f, "continue statement not within a loop"),
+            ErrorKind::CaseOutsideSwitch => write!(f, "case label not within a switch statement"),
+            ErrorKind::DefaultOutsideSwitch => write!(f, "default label not within a switch statement"),
+            ErrorKind::DuplicateCase => write!(f, "duplicate case value"),
+            ErrorKind::SwitchNotInteger => write!(f, "switch quantity not an integer"),
+            ErrorKind::DuplicateDefault => write!(f, "multiple default labels in one switch"),
+            ErrorKind::NotConstant => write!(f, "integer constant expression expected"),
+            ErrorKind::BadLabel => write!(f, "label name expected after goto"),
+            ErrorKind::UndefinedLabel => write!(f, "label used but not defined"),
+            ErrorKind::DuplicateLabel => write!(f, "duplicate label"),
+            ErrorKind::UnusedLabel => write!(f, "label defined but not used"),
+            ErrorKind::BadArraySize => write!(f, "array size must be positive"),
+            ErrorKind::TypeTooLarge => write!(f, "size of type is too large"),
+            ErrorKind::BadInitializer => write!(f, "invalid initializer"),
+            ErrorKind::ExcessInitializers => write!(f, "excess elements in initializer"),
+            ErrorKind::IncompleteType => write!(f, "incomplete type"),
+            ErrorKind::BadMemberAccess => write!(f, "member access requires a struct or union"),
+            ErrorKind::NoSuchMember(name) => write!(f, "no member named '{}'", name),
+            ErrorKind::AggregateByValue => write!(f, "structs and unions can only be used through pointers here"),
+            ErrorKind::UndeclaredFunction(name) => write!(f, "call to undeclared function '{}'", name),
+            ErrorKind::ConflictingTypes(name) => write!(f, "conflicting types for '{}'", name),
+            ErrorKind::VariadicDefinition => write!(f, "functions taking '...' can only be declared"),
+            ErrorKind::TooFewArguments(Some(name)) => write!(f, "too few arguments to function '{}'", name),
+            ErrorKind::TooFewArguments(None) => write!(f, "too few arguments in call through function pointer"),
+            ErrorKind::TooManyArguments(Some(name)) => write!(f, "too many arguments to function '{}'", name),
+            ErrorKind::TooManyArguments(None) => write!(f, "too many arguments in call through function pointer"),
+            ErrorKind::IntToPointer(n) => write!(f, "passing argument {} makes pointer from integer without a cast", n),
+            ErrorKind::PointerToInt(n) => write!(f, "passing argument {} makes integer from pointer without a cast", n),
+            ErrorKind::IncompatiblePointer(n) => write!(f, "passing argument {} from incompatible pointer type", n),
+            ErrorKind::IncompatibleArgument(n) => write!(f, "incompatible type for argument {}", n),
+            ErrorKind::UndefinedFunction(name) => write!(f, "function '{}' is called but never defined", name),
+            ErrorKind::BuiltinAddress(name) => write!(f, "cannot take the address of built-in function '{}'", name),
+            ErrorKind::BadTypeSpecifiers => write!(f, "invalid combination of type specifiers"),
+            ErrorKind::BadOperands => write!(f, "invalid operands for this operator"),
+            ErrorKind::VoidValue => write!(f, "void value not ignored as it ought to be"),
+            ErrorKind::ReturnValueInVoid => write!(f, "'return' with a value, in function returning void"),
+            ErrorKind::ReturnWithoutValue => write!(f, "'return' with no value, in function returning non-void"),
+            ErrorKind::MissingReturn => write!(f, "control reaches end of non-void function"),
+            ErrorKind::MainNotDefined => write!(f, "main() not defined"),
+            ErrorKind::ProgramTooLarge => write!(f, "program too large: the code segment is full"),
+            ErrorKind::DataTooLarge => write!(f, "program too large: the data segment is full"),
+            ErrorKind::FrameTooLarge => write!(f, "local variables too large for the stack"),
+            ErrorKind::Internal(tk) => write!(f, "compiler error tk={}", tk),
+        }
+    }
+}
+
+// Extra information attached to an error, pointing at a related location
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Note {
+    pub message: String, // What the location has to do with the error
+    pub span: Span, // The related location
+}
+
+// A compile error and the source location it was detected at
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompileError {
+    pub kind: ErrorKind, // What went wrong
+    pub span: Span, // Where it went wrong
+    pub notes: Vec<Note>, // Related locations, e.g. a previous definition
+}
+
+impl CompileError {
+    // Creates an error of the given kind at a source location
+    pub fn new(kind: ErrorKind, span: Span) -> CompileError {
+        CompileError { kind, span, notes: Vec::new() }
+    }
+
+    // Attaches a note pointing at a related location
+    pub fn with_note(mut self, message: &str, span: Span) -> CompileError {
+        self.notes.push(Note { message: message.to_string(), span });
+        self
+    }
+
+    // Line the error was detected on
+    pub fn line(&self) -> usize {
+        self.span.line
+    }
+
+    // Column the error was detected at
+    pub fn column(&self) -> usize {
+        self.span.column
+    }
+}
+
+// Formats as "line: message", the format the compiler has always printed
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span.line, self.kind)
+    }
+}
+
+impl std::error::Error for CompileError {}
@@ -0,0 +1,23 @@
+// C4: a small C compiler and the stack-based virtual machine that runs its output.
+//
+// A `Compiler` runs the preprocessor, then owns the lexer, symbol table and code buffer for one translation
+// unit and produces a `Program`; a `Vm` owns the stack, registers and data
+// segment needed to run it. Neither touches global state, so any number of them
+// can be used side by side, including on different threads.
+
+// Token and opcode names follow the original C4 spelling
+#![allow(non_upper_case_globals)]
+
+pub mod compiler;
+pub mod diagnostic;
+pub mod error;
+pub mod opcode;
+pub mod preprocess;
+pub mod token;
+pub mod types;
+pub mod vm;
+
+pub use compiler::{Compiler, Program};
+pub use error::{CompileError, ErrorKind, Note, Severity, Span};
+pub use preprocess::SourceFile;
+pub use vm::Vm;
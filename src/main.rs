@@ -0,0 +1,146 @@
+// Command-line front end for the C4 compiler/interpreter
+
+use c4::compiler::MAX_ERRORS;
+use c4::{diagnostic, Compiler, Vm};
+
+// Command-line summary printed for -h, --help and bad options
+const USAGE: &str = "usage: c4 [-s] [-d] [-I dir] [-D name[=value]] [--max-errors=N] [--diagnostics-format=text|json] file ...";
+
+// Compiles and runs the file named on the command line, returning the exit code
+fn run(mut args: Vec<String>) -> i64 {
+    let mut src = false; // Flag for source output
+    let mut debug = false; // Flag for debug output
+    let mut max_errors = MAX_ERRORS; // Number of errors reported before giving up
+    let mut json = false; // Flag for JSON diagnostics
+    let mut include_paths = Vec::new(); // #include search path (-I)
+    let mut defines = Vec::new(); // Predefined macros (-D)
+
+    // Process command-line arguments
+    args.remove(0); // Skip program name
+    while let Some(arg) = args.first().filter(|a| a.starts_with('-')) {
+        // Source output flag (-s)
+        if arg.starts_with("-s") {
+            src = true;
+        }
+        // Debug flag (-d)
+        else if arg.starts_with("-d") {
+            debug = true;
+        }
+        // Include search path (-Idir or -I dir)
+        else if let Some(dir) = arg.strip_prefix("-I") {
+            if dir.is_empty() {
+                if args.len() < 2 {
+                    println!("missing directory after -I");
+                    return -1;
+                }
+                include_paths.push(args.remove(1));
+            } else {
+                include_paths.push(dir.to_string());
+            }
+        }
+        // Macro definition (-Dname or -Dname=value)
+        else if let Some(def) = arg.strip_prefix("-D") {
+            let (name, value) = def.split_once('=').unwrap_or((def, "1"));
+            defines.push((name.to_string(), value.to_string()));
+        }
+        // Error cap (--max-errors=N)
+        else if let Some(n) = arg.strip_prefix("--max-errors=") {
+            match n.parse() {
+                Ok(n) if n > 0 => max_errors = n,
+                _ => {
+                    println!("bad error limit: {}", n);
+                    return -1;
+                }
+            }
+        }
+        // Diagnostics format (--diagnostics-format=text|json)
+        else if let Some(format) = arg.strip_prefix("--diagnostics-format=") {
+            match format {
+                "text" => json = false,
+                "json" => json = true,
+                _ => {
+                    println!("unknown diagnostics format: {}", format);
+                    return -1;
+                }
+            }
+        }
+        // Help (-h or --help)
+        else if arg == "-h" || arg == "--help" {
+            println!("{}", USAGE);
+            return 0;
+        } else {
+            println!("unknown option: {}", arg);
+            println!("{}", USAGE);
+            return -1;
+        }
+        args.remove(0);
+    }
+    // Ensure a source file is provided
+    if args.is_empty() {
+        println!("{}", USAGE);
+        return -1;
+    }
+
+    // Read source file
+    let source = match std::fs::read(&args[0]) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            report(json, &args[0], "io-error", &format!("could not open({})", args[0]));
+            return -1;
+        }
+    };
+    if source.is_empty() {
+        report(json, &args[0], "io-error", "read() returned 0");
+        return -1;
+    }
+
+    // Compile the program, reporting every error found
+    let mut compiler = Compiler::new();
+    compiler.listing = src;
+    compiler.max_errors = max_errors;
+    for dir in include_paths {
+        compiler.add_include_path(dir);
+    }
+    for (name, value) in &defines {
+        compiler.define(name, value);
+    }
+    let source = String::from_utf8_lossy(&source);
+    let result = compiler.compile_file(&args[0], &source);
+    let errors = result.as_ref().err().map_or(&[][..], Vec::as_slice);
+    // Warnings and errors together, in source order
+    let mut diagnostics: Vec<_> = compiler.warnings().iter().chain(errors).collect();
+    diagnostics.sort_by_key(|d| (d.span.file, d.span.line, d.span.column));
+    for err in diagnostics {
+        if json {
+            println!("{}", diagnostic::render_json(err, compiler.files()));
+        } else {
+            print!("{}", diagnostic::render(err, compiler.files()));
+        }
+    }
+    let Ok(program) = result else {
+        return -1;
+    };
+    // Exit if source output mode
+    if src {
+        return 0;
+    }
+
+    // Execute generated code
+    let mut vm = Vm::new(program);
+    vm.debug = debug;
+    vm.run(&args)
+}
+
+// Prints an error that has no source location in the selected diagnostics format
+fn report(json: bool, file: &str, code: &str, message: &str) {
+    if json {
+        println!("{}", diagnostic::render_json_message(file, code, message));
+    } else {
+        println!("{}", message);
+    }
+}
+
+// Entry point for the C4 compiler/interpreter
+fn main() {
+    std::process::exit(run(std::env::args().collect()) as i32);
+}
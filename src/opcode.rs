@@ -0,0 +1,123 @@
+// Instruction set of the C4 virtual machine
+
+// Define OpCode type for intermediate code instructions
+pub type OpCode = i64;
+// Constants for operation codes used in code generation
+pub const FGE: OpCode = 74; // Floating-point greater or equal
+pub const FLE: OpCode = 73; // Floating-point less or equal
+pub const FGT: OpCode = 72; // Floating-point greater than
+pub const FLT: OpCode = 71; // Floating-point less than
+pub const FNE: OpCode = 70; // Floating-point not equal
+pub const FEQ: OpCode = 69; // Floating-point equal
+pub const FDIV: OpCode = 68; // Floating-point division
+pub const FMUL: OpCode = 67; // Floating-point multiplication
+pub const FSUB: OpCode = 66; // Floating-point subtraction
+pub const FADD: OpCode = 65; // Floating-point addition
+pub const DTOF: OpCode = 64; // Round a double to float precision
+pub const FTOU: OpCode = 63; // Floating point to unsigned integer
+pub const FTOI: OpCode = 62; // Floating point to signed integer
+pub const UTOF: OpCode = 61; // Unsigned integer to floating point
+pub const ITOF: OpCode = 60; // Signed integer to floating point
+pub const SF: OpCode = 59; // Store float
+pub const LF: OpCode = 58; // Load float
+pub const SWAP: OpCode = 57; // Exchange the accumulator with the top of the stack
+pub const GEU: OpCode = 56; // Unsigned greater or equal
+pub const LEU: OpCode = 55; // Unsigned less or equal
+pub const GTU: OpCode = 54; // Unsigned greater than
+pub const LTU: OpCode = 53; // Unsigned less than
+pub const SHRU: OpCode = 52; // Logical shift right
+pub const MODU: OpCode = 51; // Unsigned modulo of values the operand's number of bytes wide
+pub const DIVU: OpCode = 50; // Unsigned division of values the operand's number of bytes wide
+pub const ZEXT: OpCode = 49; // Zero-extend the operand's number of low bytes
+pub const SEXT: OpCode = 48; // Sign-extend the operand's number of low bytes
+pub const SW: OpCode = 47; // Store 32-bit word
+pub const SS: OpCode = 46; // Store short
+pub const LWU: OpCode = 45; // Load unsigned 32-bit word
+pub const LSU: OpCode = 44; // Load unsigned short
+pub const LCU: OpCode = 43; // Load unsigned char
+pub const LW: OpCode = 42; // Load 32-bit word
+pub const LS: OpCode = 41; // Load short
+pub const JSRI: OpCode = 40; // Jump to the subroutine whose address is on the stack above the arguments
+pub const JTAB: OpCode = 39; // Jump through a table indexed by the accumulator
+pub const EXIT: OpCode = 38; // Exit program
+pub const MCMP: OpCode = 37; // Memory compare
+pub const MSET: OpCode = 36; // Memory set
+pub const FREE: OpCode = 35; // Free memory
+pub const MALC: OpCode = 34; // Allocate memory
+pub const PRTF: OpCode = 33; // Print formatted
+pub const CLOS: OpCode = 32; // Close file
+pub const READ: OpCode = 31; // Read file
+pub const OPEN: OpCode = 30; // Open file
+pub const MOD: OpCode = 29; // Modulo
+pub const DIV: OpCode = 28; // Division
+pub const MUL: OpCode = 27; // Multiplication
+pub const SUB: OpCode = 26; // Subtraction
+pub const ADD: OpCode = 25; // Addition
+pub const SHR: OpCode = 24; // Shift right
+pub const SHL: OpCode = 23; // Shift left
+pub const GE: OpCode = 22; // Greater or equal
+pub const LE: OpCode = 21; // Less or equal
+pub const GT: OpCode = 20; // Greater than
+pub const LT: OpCode = 19; // Less than
+pub const NE: OpCode = 18; // Not equal
+pub const EQ: OpCode = 17; // Equal
+pub const AND: OpCode = 16; // Bitwise AND
+pub const XOR: OpCode = 15; // Bitwise XOR
+pub const OR: OpCode = 14; // Bitwise OR
+pub const PSH: OpCode = 13; // Push to stack
+pub const SC: OpCode = 12; // Store char
+pub const SI: OpCode = 11; // Store int
+pub const LC: OpCode = 10; // Load char
+pub const LI: OpCode = 9; // Load int
+pub const LEV: OpCode = 8; // Leave function
+pub const ADJ: OpCode = 7; // Adjust stack
+pub const ENT: OpCode = 6; // Enter function
+pub const BNZ: OpCode = 5; // Branch if not zero
+pub const BZ: OpCode = 4; // Branch if zero
+pub const JSR: OpCode = 3; // Jump to subroutine
+pub const JMP: OpCode = 2; // Jump
+pub const IMM: OpCode = 1; // Immediate value
+pub const LEA: OpCode = 0; // Load effective address
+
+// Printable opcode names, one per opcode in numeric order
+const OP_NAMES: [&str; 75] = [
+    "LEA ", "IMM ", "JMP ", "JSR ", "BZ  ", "BNZ ", "ENT ", "ADJ ", "LEV ", "LI  ", "LC  ", "SI  ", "SC  ",
+    "PSH ", "OR  ", "XOR ", "AND ", "EQ  ", "NE  ", "LT  ", "GT  ", "LE  ", "GE  ", "SHL ", "SHR ", "ADD ",
+    "SUB ", "MUL ", "DIV ", "MOD ", "OPEN", "READ", "CLOS", "PRTF", "MALC", "FREE", "MSET", "MCMP", "EXIT",
+    "JTAB", "JSRI", "LS  ", "LW  ", "LCU ", "LSU ", "LWU ", "SS  ", "SW  ", "SEXT", "ZEXT", "DIVU", "MODU", "SHRU",
+    "LTU ", "GTU ", "LEU ", "GEU ", "SWAP", "LF  ", "SF  ", "ITOF", "UTOF", "FTOI", "FTOU", "DTOF", "FADD", "FSUB",
+    "FMUL", "FDIV", "FEQ ", "FNE ", "FLT ", "FGT ", "FLE ", "FGE ",
+];
+
+// Sign-extends the low `n` bytes of `v`, or zero-extends them, as SEXT and ZEXT do
+pub fn extend(v: i64, n: i64, unsigned: bool) -> i64 {
+    if n >= 8 {
+        return v;
+    }
+    let bits = (8 - n as u32) * 8;
+    if unsigned {
+        ((v as u64) << bits >> bits) as i64
+    } else {
+        v << bits >> bits
+    }
+}
+
+// Floating-point values are kept in the accumulator, on the stack and in doubles in
+// memory as the bits of an f64; these convert between the two
+pub fn float(v: i64) -> f64 {
+    f64::from_bits(v as u64)
+}
+
+pub fn bits(x: f64) -> i64 {
+    x.to_bits() as i64
+}
+
+// Returns the four-character name of an opcode for listings and traces
+pub fn op_name(op: OpCode) -> &'static str {
+    usize::try_from(op).ok().and_then(|i| OP_NAMES.get(i)).copied().unwrap_or("????")
+}
+
+// Returns true if the opcode is followed by an operand word in the code segment
+pub fn has_operand(op: OpCode) -> bool {
+    op <= ADJ || matches!(op, JTAB | JSRI | SEXT | ZEXT | DIVU | MODU)
+}
@@ -0,0 +1,1170 @@
+// C preprocessor run in front of the lexer: #define/#undef, conditional compilation
+// (#if/#ifdef/#ifndef/#elif/#else/#endif) and #include with search paths.
+//
+// The output keeps one line per input line (directives and skipped lines become empty
+// lines, included files are spliced in place), and records for every output line the
+// file and line it came from so diagnostics can point back at the original source.
+
+use std::collections::{HashMap, VecDeque};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use crate::error::{CompileError, ErrorKind, Span};
+
+// Maximum nesting depth of #include
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+// Headers built into the compiler, declaring the library functions the virtual
+// machine provides
+const BUILTIN_HEADERS: [(&str, &str); 5] = [
+    ("fcntl.h", include_str!("../include/fcntl.h")),
+    ("stdio.h", include_str!("../include/stdio.h")),
+    ("stdlib.h", include_str!("../include/stdlib.h")),
+    ("string.h", include_str!("../include/string.h")),
+    ("unistd.h", include_str!("../include/unistd.h")),
+];
+
+// Multi-character punctuators, longest first
+const PUNCTUATORS: [&str; 23] = [
+    "...", "<<=", ">>=", "##", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "++", "--", "->", "+=", "-=", "*=",
+    "/=", "%=", "&=", "|=", "^=",
+];
+
+// A file read by the preprocessor, kept for rendering diagnostics
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    pub name: String, // Path as given on the command line or in #include
+    pub text: String, // Contents
+}
+
+// Where a line of preprocessed output came from
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineOrigin {
+    pub file: usize, // Index into the preprocessor's files
+    pub line: usize, // Line number in that file
+    pub offset: usize, // Byte offset of the start of that line
+    pub shifts: Vec<Shift>, // Pieces of the line moved by macro expansions, left to right
+}
+
+// A piece of an output line at other columns than in its source line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shift {
+    pub out: usize, // Output column where the piece starts
+    pub src: usize, // Source column of its first character
+    pub expansion: bool, // A macro expansion: all of it is at the invocation's column
+}
+
+impl LineOrigin {
+    // Source column of column `col` of the output line
+    pub fn column(&self, col: usize) -> usize {
+        match self.shifts.iter().rev().find(|s| s.out <= col) {
+            Some(s) if s.expansion => s.src,
+            Some(s) => s.src + (col - s.out),
+            None => col,
+        }
+    }
+}
+
+// Kinds of preprocessing tokens
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TokKind {
+    Ident, // Identifier or keyword
+    Number, // Preprocessing number
+    Str, // String or character literal
+    Punct, // Operator or punctuator
+    Space, // Horizontal whitespace or a backslash-newline
+    Comment, // Comment, kept verbatim outside directives
+    Newline, // End of line
+}
+
+// A preprocessing token
+#[derive(Clone, Debug)]
+struct Tok {
+    kind: TokKind,
+    text: String, // Spelling
+    pos: usize, // Byte offset in its file
+    line: usize, // Line in its file
+    col: usize, // Column in its file
+    hide: Vec<String>, // Macros that must not be expanded again in this token
+    paste: bool, // A '##' operator from a macro body
+}
+
+impl Tok {
+    // Returns true for the punctuator `p`
+    fn is(&self, p: &str) -> bool {
+        self.kind == TokKind::Punct && self.text == p
+    }
+
+    // Returns true for tokens that only separate other tokens
+    fn is_space(&self) -> bool {
+        matches!(self.kind, TokKind::Space | TokKind::Comment | TokKind::Newline)
+    }
+
+    // A token synthesized by the preprocessor at the position of `at`
+    fn synth(kind: TokKind, text: String, at: &Tok) -> Tok {
+        Tok { kind, text, pos: at.pos, line: at.line, col: at.col, hide: at.hide.clone(), paste: false }
+    }
+}
+
+// A macro definition
+#[derive(Clone, Debug)]
+struct Macro {
+    params: Option<Vec<String>>, // Parameter names for function-like macros
+    variadic: bool, // Last parameter is `...`, spelled __VA_ARGS__ in the body
+    body: Vec<Tok>, // Replacement list
+}
+
+// State of one #if/#ifdef/#ifndef group
+struct Cond {
+    active: bool, // Lines of the current branch are kept
+    taken: bool, // Some branch of the group has been kept
+    parent: bool, // The enclosing group is active
+    seen_else: bool, // #else has been seen
+    span: Span, // The opening directive
+}
+
+// Preprocessor state: macro table, include paths and the output being built
+#[derive(Default)]
+pub struct Preprocessor {
+    pub include_paths: Vec<PathBuf>, // Directories searched by #include
+    files: Vec<SourceFile>, // Every file read so far
+    macros: HashMap<String, Macro>, // Macro table
+    out: String, // Preprocessed text
+    lines: Vec<LineOrigin>, // Origin of every output line
+    errors: Vec<CompileError>, // Errors reported so far
+    depth: usize, // Current #include nesting depth
+    glue: bool, // Last output came from a macro expansion
+}
+
+// Splits source text into preprocessing tokens; also returns the token of an
+// unterminated block comment, if any
+fn lex(text: &str) -> (Vec<Tok>, Option<usize>) {
+    let b = text.as_bytes();
+    let mut toks = Vec::new();
+    let mut unterminated = None;
+    let (mut i, mut line, mut line_start) = (0, 1, 0);
+    while i < b.len() {
+        let start = i;
+        let c = b[i];
+        let next = b.get(i + 1).copied().unwrap_or(0);
+        let kind = if c == b'\n' {
+            i += 1;
+            TokKind::Newline
+        } else if matches!(c, b' ' | b'\t' | b'\r' | 0x0b | 0x0c) {
+            while i < b.len() && matches!(b[i], b' ' | b'\t' | b'\r' | 0x0b | 0x0c) {
+                i += 1;
+            }
+            TokKind::Space
+        } else if c == b'\\' && next == b'\n' {
+            i += 2; // Line splice
+            TokKind::Space
+        } else if c == b'/' && next == b'/' {
+            while i < b.len() && b[i] != b'\n' {
+                i += 1;
+            }
+            TokKind::Comment
+        } else if c == b'/' && next == b'*' {
+            i += 2;
+            while i < b.len() && !(b[i] == b'*' && b.get(i + 1) == Some(&b'/')) {
+                i += 1;
+            }
+            if i < b.len() {
+                i += 2;
+            } else {
+                unterminated = Some(toks.len());
+            }
+            TokKind::Comment
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            while i < b.len() && (b[i].is_ascii_alphanumeric() || b[i] == b'_') {
+                i += 1;
+            }
+            TokKind::Ident
+        } else if c.is_ascii_digit() || (c == b'.' && next.is_ascii_digit()) {
+            i += 1;
+            while i < b.len()
+                && (b[i].is_ascii_alphanumeric()
+                    || b[i] == b'_'
+                    || b[i] == b'.'
+                    || (matches!(b[i], b'+' | b'-') && matches!(b[i - 1], b'e' | b'E' | b'p' | b'P')))
+            {
+                i += 1;
+            }
+            TokKind::Number
+        } else if c == b'"' || c == b'\'' {
+            i += 1;
+            while i < b.len() && b[i] != c && b[i] != b'\n' {
+                i += if b[i] == b'\\' && i + 1 < b.len() { 2 } else { 1 };
+            }
+            if i < b.len() && b[i] == c {
+                i += 1;
+            }
+            TokKind::Str
+        } else {
+            match PUNCTUATORS.iter().find(|p| text[i..].starts_with(*p)) {
+                Some(p) => i += p.len(),
+                None => i += text[i..].chars().next().map_or(1, char::len_utf8),
+            }
+            TokKind::Punct
+        };
+        let tok_text = &text[start..i];
+        toks.push(Tok {
+            kind,
+            text: tok_text.to_string(),
+            pos: start,
+            line,
+            col: start - line_start + 1,
+            hide: Vec::new(),
+            paste: false,
+        });
+        // Keep line counting right for tokens that span lines
+        if let Some(last) = tok_text.rfind('\n') {
+            line += tok_text.matches('\n').count();
+            line_start = start + last + 1;
+        }
+    }
+    (toks, unterminated)
+}
+
+// Returns true if `a` followed directly by `b` could lex as different tokens
+fn needs_space(a: char, b: char) -> bool {
+    let ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let punct = |c: char| "+-*/%<>=!&|^.#:".contains(c);
+    (ident(a) && ident(b)) || (punct(a) && punct(b))
+}
+
+// Index of the first non-space token at or after `i`
+fn skip_space(toks: &[Tok], mut i: usize) -> usize {
+    while i < toks.len() && toks[i].is_space() {
+        i += 1;
+    }
+    i
+}
+
+// Strips leading and trailing whitespace from a token list
+fn trim(toks: &[Tok]) -> Vec<Tok> {
+    let start = skip_space(toks, 0);
+    let end = toks.iter().rposition(|t| !t.is_space()).map_or(start, |e| e + 1);
+    toks[start..end.max(start)].to_vec()
+}
+
+// Spells a macro argument as a string literal for the `#` operator
+fn stringify(arg: &[Tok], at: &Tok) -> Tok {
+    let mut s = String::from("\"");
+    let mut space = false;
+    for t in arg {
+        if t.is_space() {
+            space = true;
+            continue;
+        }
+        if space && s.len() > 1 {
+            s.push(' ');
+        }
+        space = false;
+        if t.kind == TokKind::Str {
+            for c in t.text.chars() {
+                if c == '"' || c == '\\' {
+                    s.push('\\');
+                }
+                s.push(c);
+            }
+        } else {
+            s.push_str(&t.text);
+        }
+    }
+    s.push('"');
+    Tok::synth(TokKind::Str, s, at)
+}
+
+// Spells `text` as a C string literal: quotes and backslashes are escaped, and control
+// characters written as octal escapes
+fn quote(text: &str) -> String {
+    let mut s = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                s.push('\\');
+                s.push(c);
+            }
+            _ if c.is_ascii_control() => s.push_str(&format!("\\{:03o}", c as u8)),
+            _ => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+// Joins the operands of every `##` in a replacement list
+fn paste(toks: Vec<Tok>) -> Vec<Tok> {
+    let mut out: Vec<Tok> = Vec::new();
+    let mut it = toks.into_iter().peekable();
+    while let Some(t) = it.next() {
+        if !t.paste {
+            out.push(t);
+            continue;
+        }
+        while out.last().is_some_and(Tok::is_space) {
+            out.pop();
+        }
+        while it.peek().is_some_and(Tok::is_space) {
+            it.next();
+        }
+        let rhs = it.next();
+        let lhs = out.pop();
+        let text = format!("{}{}", lhs.as_ref().map_or("", |l| &l.text), rhs.as_ref().map_or("", |r| &r.text));
+        let at = lhs.as_ref().or(rhs.as_ref()).unwrap_or(&t).clone();
+        let (mut pasted, _) = lex(&text);
+        for p in pasted.iter_mut() {
+            p.pos = at.pos;
+            p.line = at.line;
+            p.col = at.col;
+            p.hide = at.hide.clone();
+        }
+        if pasted.is_empty() {
+            // Both operands were empty: keep a placemarker so a later '##' has an operand
+            pasted.push(Tok::synth(TokKind::Space, String::new(), &at));
+        }
+        out.extend(pasted);
+    }
+    out
+}
+
+// Splits the arguments of a macro call; `toks[open]` is its '('. Returns the arguments
+// and the index of the closing ')', or None if the call is unterminated
+fn collect_args(toks: &[Tok], open: usize) -> Option<(Vec<Vec<Tok>>, usize)> {
+    let mut args = vec![Vec::new()];
+    let mut depth = 0;
+    for (k, t) in toks.iter().enumerate().skip(open + 1) {
+        if t.is("(") {
+            depth += 1;
+        } else if t.is(")") {
+            if depth == 0 {
+                return Some((args.iter().map(|a| trim(a)).collect(), k));
+            }
+            depth -= 1;
+        } else if t.is(",") && depth == 0 {
+            args.push(Vec::new());
+            continue;
+        }
+        if let Some(arg) = args.last_mut() {
+            arg.push(t.clone());
+        }
+    }
+    None
+}
+
+// Value of a number or character constant in an #if expression
+fn constant_value(t: &str) -> Option<i64> {
+    if let Some(body) = t.strip_prefix('\'') {
+        let body = body.strip_suffix('\'')?;
+        let mut chars = body.chars();
+        return match chars.next()? {
+            '\\' => {
+                let rest: String = chars.collect();
+                match rest.as_str() {
+                    "n" => Some(10),
+                    "t" => Some(9),
+                    "r" => Some(13),
+                    "a" => Some(7),
+                    "b" => Some(8),
+                    "f" => Some(12),
+                    "v" => Some(11),
+                    _ if rest.starts_with('x') => i64::from_str_radix(&rest[1..], 16).ok(),
+                    _ if rest.starts_with(|c: char| c.is_digit(8)) => i64::from_str_radix(&rest, 8).ok(),
+                    _ => rest.chars().next().map(|c| c as i64),
+                }
+            }
+            c => Some(c as i64),
+        };
+    }
+    let digits = t.trim_end_matches(['u', 'U', 'l', 'L']);
+    if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok().map(|v| v as i64)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8).ok().map(|v| v as i64)
+    } else {
+        digits.parse::<u64>().ok().map(|v| v as i64)
+    }
+}
+
+// Evaluator for the constant expression of #if and #elif
+struct Eval<'a> {
+    toks: &'a [Tok],
+    i: usize,
+}
+
+impl Eval<'_> {
+    // Returns true and consumes the next token if it is the punctuator `p`
+    fn eat(&mut self, p: &str) -> bool {
+        if self.toks.get(self.i).is_some_and(|t| t.is(p)) {
+            self.i += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Conditional expression: binary ( '?' cond ':' cond )?
+    fn cond(&mut self) -> Option<i64> {
+        let c = self.binary(1)?;
+        if !self.eat("?") {
+            return Some(c);
+        }
+        let a = self.cond()?;
+        if !self.eat(":") {
+            return None;
+        }
+        let b = self.cond()?;
+        Some(if c != 0 { a } else { b })
+    }
+
+    // Binary operators of precedence `lev` and above
+    fn binary(&mut self, lev: usize) -> Option<i64> {
+        const LEVELS: [&[&str]; 10] = [
+            &["||"],
+            &["&&"],
+            &["|"],
+            &["^"],
+            &["&"],
+            &["==", "!="],
+            &["<", ">", "<=", ">="],
+            &["<<", ">>"],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+        if lev > LEVELS.len() {
+            return self.unary();
+        }
+        let mut a = self.binary(lev + 1)?;
+        while let Some(op) = LEVELS[lev - 1].iter().find(|op| self.toks.get(self.i).is_some_and(|t| t.is(op))) {
+            self.i += 1;
+            let b = self.binary(lev + 1)?;
+            a = match *op {
+                "||" => (a != 0 || b != 0) as i64,
+                "&&" => (a != 0 && b != 0) as i64,
+                "|" => a | b,
+                "^" => a ^ b,
+                "&" => a & b,
+                "==" => (a == b) as i64,
+                "!=" => (a != b) as i64,
+                "<" => (a < b) as i64,
+                ">" => (a > b) as i64,
+                "<=" => (a <= b) as i64,
+                ">=" => (a >= b) as i64,
+                "<<" => a.wrapping_shl(b as u32),
+                ">>" => a.wrapping_shr(b as u32),
+                "+" => a.wrapping_add(b),
+                "-" => a.wrapping_sub(b),
+                "*" => a.wrapping_mul(b),
+                "/" => a.checked_div(b)?,
+                _ => a.checked_rem(b)?,
+            };
+        }
+        Some(a)
+    }
+
+    // Unary operators and primary expressions
+    fn unary(&mut self) -> Option<i64> {
+        if self.eat("-") {
+            return self.unary().map(i64::wrapping_neg);
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        if self.eat("!") {
+            return self.unary().map(|v| (v == 0) as i64);
+        }
+        if self.eat("~") {
+            return self.unary().map(|v| !v);
+        }
+        if self.eat("(") {
+            let v = self.cond()?;
+            return if self.eat(")") { Some(v) } else { None };
+        }
+        let t = self.toks.get(self.i)?;
+        self.i += 1;
+        match t.kind {
+            TokKind::Number | TokKind::Str => constant_value(&t.text),
+            TokKind::Ident => Some(0), // Identifiers left after expansion are 0
+            _ => None,
+        }
+    }
+}
+
+impl Preprocessor {
+    // Creates a preprocessor with only the built-in macros defined
+    pub fn new() -> Preprocessor {
+        let mut pp = Preprocessor::default();
+        pp.define("__C4__", "1");
+        pp
+    }
+
+    // Defines an object-like macro, as `-D name=value` would
+    pub fn define(&mut self, name: &str, value: &str) {
+        let (body, _) = lex(value);
+        let body = trim(&body);
+        self.macros.insert(name.to_string(), Macro { params: None, variadic: false, body });
+    }
+
+    // Removes a macro definition
+    pub fn undefine(&mut self, name: &str) {
+        self.macros.remove(name);
+    }
+
+    // Every file read so far, indexed by `Span::file` of the errors reported
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    // Preprocesses a translation unit, returning the expanded text and the origin of
+    // each of its lines. Each run starts with the macros defined through `define`, so
+    // the definitions of one translation unit do not carry over into the next.
+    pub fn run(&mut self, name: &str, text: &str) -> Result<(String, Vec<LineOrigin>), Vec<CompileError>> {
+        self.out.clear();
+        self.errors.clear();
+        self.files.clear();
+        self.files.push(SourceFile { name: name.to_string(), text: text.to_string() });
+        let file = 0;
+        self.lines = vec![LineOrigin { file, line: 1, offset: 0, shifts: Vec::new() }];
+        let predefined = self.macros.clone();
+        self.process(file);
+        self.macros = predefined;
+        if !self.errors.is_empty() {
+            return Err(std::mem::take(&mut self.errors));
+        }
+        Ok((std::mem::take(&mut self.out), std::mem::take(&mut self.lines)))
+    }
+
+    // Records an error at a token of the given file
+    fn error(&mut self, kind: ErrorKind, file: usize, at: &Tok) {
+        let span = Span { start: at.pos, end: at.pos + at.text.len(), line: at.line, column: at.col, file };
+        self.errors.push(CompileError::new(kind, span));
+    }
+
+    // Ends the current output line; the next one comes from `line` of `file`
+    fn newline(&mut self, file: usize, line: usize, offset: usize) {
+        self.out.push('\n');
+        self.lines.push(LineOrigin { file, line, offset, shifts: Vec::new() });
+    }
+
+    // Records that the output that follows on the current line comes from source
+    // column `src`, unless the line's columns already say so
+    fn shift(&mut self, src: usize, expansion: bool) {
+        let out = self.out.len() - self.out.rfind('\n').map_or(0, |k| k + 1) + 1;
+        let Some(line) = self.lines.last_mut() else {
+            return;
+        };
+        let last = line.shifts.last();
+        let known = if expansion {
+            last.is_some_and(|s| s.expansion && s.src == src)
+        } else {
+            !last.is_some_and(|s| s.expansion) && line.column(out) == src
+        };
+        if known {
+            return;
+        }
+        line.shifts.push(Shift { out, src, expansion });
+    }
+
+    // Emits the newlines inside a source token, keeping the line table in step
+    fn newlines_in(&mut self, file: usize, t: &Tok) {
+        for (n, (k, _)) in t.text.match_indices('\n').enumerate() {
+            self.newline(file, t.line + n + 1, t.pos + k + 1);
+        }
+    }
+
+    // Emits a source token verbatim
+    fn emit_source(&mut self, file: usize, t: &Tok) {
+        if self.glue {
+            if let (Some(a), Some(b)) = (self.out.chars().last(), t.text.chars().next()) {
+                if needs_space(a, b) {
+                    self.out.push(' ');
+                }
+            }
+            self.glue = false;
+        }
+        self.shift(t.col, false);
+        let mut lines = t.text.split('\n');
+        self.out.push_str(lines.next().unwrap_or(""));
+        for (n, (k, _)) in t.text.match_indices('\n').enumerate() {
+            self.newline(file, t.line + n + 1, t.pos + k + 1);
+            self.out.push_str(lines.next().unwrap_or(""));
+        }
+    }
+
+    // Emits the result of the macro invocation at `at`, spacing tokens that would
+    // otherwise merge. Tokens of the arguments, found at byte offsets `call` of the
+    // source, keep their columns; the rest is placed at the invocation.
+    fn emit_expansion(&mut self, toks: &[Tok], at: &Tok, call: RangeInclusive<usize>) {
+        self.shift(at.col, true);
+        for t in toks {
+            if let (Some(a), Some(b)) = (self.out.chars().last(), t.text.chars().next()) {
+                if needs_space(a, b) {
+                    self.out.push(' ');
+                }
+            }
+            if !t.is_space() {
+                if call.contains(&t.pos) && t.line == at.line {
+                    self.shift(t.col, false);
+                } else {
+                    self.shift(at.col, true);
+                }
+            }
+            if t.is_space() {
+                if !self.out.ends_with(' ') && !t.text.is_empty() {
+                    self.out.push(' ');
+                }
+            } else {
+                self.out.push_str(&t.text);
+            }
+        }
+        self.glue = true;
+    }
+
+    // Preprocesses one file into the output
+    fn process(&mut self, file: usize) {
+        let text = self.files[file].text.clone();
+        let (toks, unterminated) = lex(&text);
+        let mut conds: Vec<Cond> = Vec::new();
+        let mut line_start = true;
+        let mut i = 0;
+        while i < toks.len() {
+            let active = conds.last().is_none_or(|c| c.active);
+            let t = &toks[i];
+            if t.kind == TokKind::Newline {
+                self.newline(file, t.line + 1, t.pos + 1);
+                line_start = true;
+                i += 1;
+            } else if t.is("#") && line_start {
+                i = self.directive(file, &toks, i, &mut conds);
+            } else if !active {
+                self.newlines_in(file, t); // Skipped lines still count
+                line_start &= t.is_space();
+                i += 1;
+            } else if t.kind == TokKind::Ident && self.macros.contains_key(&t.text) || t.text == "__LINE__" || t.text == "__FILE__" {
+                line_start = false;
+                i = self.expand_at(file, &toks, i);
+            } else {
+                line_start &= t.is_space() && t.kind != TokKind::Newline;
+                self.emit_source(file, t);
+                i += 1;
+            }
+        }
+        for c in conds {
+            self.errors.push(CompileError::new(ErrorKind::UnterminatedConditional, c.span));
+        }
+        if let Some(k) = unterminated {
+            self.error(ErrorKind::UnterminatedComment, file, &toks[k]);
+        }
+    }
+
+    // Handles a directive starting at the '#' token `toks[i]`; returns the index of the
+    // newline that ends it
+    fn directive(&mut self, file: usize, toks: &[Tok], i: usize, conds: &mut Vec<Cond>) -> usize {
+        let hash = &toks[i];
+        let end = toks[i..].iter().position(|t| t.kind == TokKind::Newline).map_or(toks.len(), |k| i + k);
+        // Comments and line splices inside a directive are plain whitespace
+        let line: Vec<Tok> = toks[i + 1..end]
+            .iter()
+            .map(|t| if t.is_space() { Tok { kind: TokKind::Space, text: " ".to_string(), ..t.clone() } } else { t.clone() })
+            .collect();
+        let k = skip_space(&line, 0);
+        let name = line.get(k).filter(|t| t.kind == TokKind::Ident).map_or("", |t| t.text.as_str());
+        let rest = &line[(k + 1).min(line.len())..];
+        let active = conds.last().is_none_or(|c| c.active);
+        let span = Span { start: hash.pos, end: hash.pos + 1, line: hash.line, column: hash.col, file };
+
+        match name {
+            // Conditional compilation is tracked even inside skipped groups
+            "if" | "ifdef" | "ifndef" => {
+                let v = active && self.condition(file, name, rest, hash);
+                conds.push(Cond { active: v, taken: v || !active, parent: active, seen_else: false, span });
+            }
+            "elif" => match conds.last_mut() {
+                Some(c) if !c.seen_else => {
+                    let (parent, taken) = (c.parent, c.taken);
+                    let v = parent && !taken && self.condition(file, name, rest, hash);
+                    if let Some(c) = conds.last_mut() {
+                        c.active = v;
+                        c.taken |= v;
+                    }
+                }
+                Some(_) => self.error(ErrorKind::UnmatchedDirective("#elif after #else"), file, hash),
+                None => self.error(ErrorKind::UnmatchedDirective("#elif without #if"), file, hash),
+            },
+            "else" => match conds.last_mut() {
+                Some(c) if !c.seen_else => {
+                    c.active = c.parent && !c.taken;
+                    c.taken = true;
+                    c.seen_else = true;
+                }
+                Some(_) => self.error(ErrorKind::UnmatchedDirective("#else after #else"), file, hash),
+                None => self.error(ErrorKind::UnmatchedDirective("#else without #if"), file, hash),
+            },
+            "endif" => {
+                if conds.pop().is_none() {
+                    self.error(ErrorKind::UnmatchedDirective("#endif without #if"), file, hash);
+                }
+            }
+            // Everything else only applies to lines being kept
+            _ if !active => {}
+            "define" => self.define_directive(file, rest, hash),
+            "undef" => match rest.get(skip_space(rest, 0)) {
+                Some(t) if t.kind == TokKind::Ident => {
+                    self.macros.remove(&t.text);
+                }
+                _ => self.error(ErrorKind::BadMacroDefinition, file, hash),
+            },
+            "include" => self.include(file, rest, hash),
+            "error" => {
+                let msg: String = trim(rest).iter().map(|t| t.text.as_str()).collect();
+                self.error(ErrorKind::ErrorDirective(msg), file, hash);
+            }
+            "pragma" | "line" | "warning" | "" => {}
+            _ => self.error(ErrorKind::UnknownDirective(name.to_string()), file, &line[k]),
+        }
+
+        // Keep the output in step with lines the directive continued over
+        for t in &toks[i..end] {
+            self.newlines_in(file, t);
+        }
+        end
+    }
+
+    // Evaluates the condition of #if, #elif, #ifdef or #ifndef
+    fn condition(&mut self, file: usize, directive: &str, rest: &[Tok], hash: &Tok) -> bool {
+        if directive == "ifdef" || directive == "ifndef" {
+            return match rest.get(skip_space(rest, 0)) {
+                Some(t) if t.kind == TokKind::Ident => self.macros.contains_key(&t.text) == (directive == "ifdef"),
+                _ => {
+                    self.error(ErrorKind::BadIfExpression, file, hash);
+                    false
+                }
+            };
+        }
+
+        // Replace `defined NAME` and `defined(NAME)` before expanding macros
+        let mut toks = Vec::new();
+        let mut k = 0;
+        while k < rest.len() {
+            let t = &rest[k];
+            if t.kind == TokKind::Ident && t.text == "defined" {
+                let mut j = skip_space(rest, k + 1);
+                let paren = rest.get(j).is_some_and(|t| t.is("("));
+                if paren {
+                    j = skip_space(rest, j + 1);
+                }
+                let Some(name) = rest.get(j).filter(|t| t.kind == TokKind::Ident) else {
+                    self.error(ErrorKind::BadIfExpression, file, hash);
+                    return false;
+                };
+                let v = if self.macros.contains_key(&name.text) { "1" } else { "0" };
+                toks.push(Tok::synth(TokKind::Number, v.to_string(), t));
+                if paren {
+                    j = skip_space(rest, j + 1);
+                    if !rest.get(j).is_some_and(|t| t.is(")")) {
+                        self.error(ErrorKind::BadIfExpression, file, hash);
+                        return false;
+                    }
+                }
+                k = j + 1;
+            } else {
+                toks.push(t.clone());
+                k += 1;
+            }
+        }
+
+        let expanded: Vec<Tok> = self.expand_tokens(toks, file).into_iter().filter(|t| !t.is_space()).collect();
+        let mut eval = Eval { toks: &expanded, i: 0 };
+        match eval.cond() {
+            Some(v) if eval.i == expanded.len() => v != 0,
+            _ => {
+                self.error(ErrorKind::BadIfExpression, file, hash);
+                false
+            }
+        }
+    }
+
+    // Handles #define
+    fn define_directive(&mut self, file: usize, rest: &[Tok], hash: &Tok) {
+        let k = skip_space(rest, 0);
+        let Some(name) = rest.get(k).filter(|t| t.kind == TokKind::Ident && t.text != "defined") else {
+            self.error(ErrorKind::BadMacroDefinition, file, hash);
+            return;
+        };
+        let mut j = k + 1;
+        let mut params = None;
+        let mut variadic = false;
+        // A '(' right after the name starts a parameter list
+        if rest.get(j).is_some_and(|t| t.is("(") && t.pos == name.pos + name.text.len()) {
+            let mut list = Vec::new();
+            j = skip_space(rest, j + 1);
+            if rest.get(j).is_some_and(|t| t.is(")")) {
+                j += 1;
+            } else {
+                loop {
+                    match rest.get(j) {
+                        Some(t) if t.kind == TokKind::Ident && !variadic => list.push(t.text.clone()),
+                        Some(t) if t.is("...") && !variadic => {
+                            variadic = true;
+                            list.push("__VA_ARGS__".to_string());
+                        }
+                        _ => {
+                            self.error(ErrorKind::BadMacroDefinition, file, hash);
+                            return;
+                        }
+                    }
+                    j = skip_space(rest, j + 1);
+                    match rest.get(j) {
+                        Some(t) if t.is(",") => j = skip_space(rest, j + 1),
+                        Some(t) if t.is(")") => {
+                            j += 1;
+                            break;
+                        }
+                        _ => {
+                            self.error(ErrorKind::BadMacroDefinition, file, hash);
+                            return;
+                        }
+                    }
+                }
+            }
+            params = Some(list);
+        }
+        let body = trim(&rest[j.min(rest.len())..])
+            .into_iter()
+            .map(|t| Tok { paste: t.is("##"), ..t })
+            .collect();
+        self.macros.insert(name.text.clone(), Macro { params, variadic, body });
+    }
+
+    // Handles #include "file" and #include <file>
+    fn include(&mut self, file: usize, rest: &[Tok], hash: &Tok) {
+        let mut toks = trim(rest);
+        // A macro may expand to the file name
+        if toks.first().is_some_and(|t| t.kind == TokKind::Ident) {
+            toks = trim(&self.expand_tokens(toks, file));
+        }
+        let (name, quoted) = match toks.first() {
+            Some(t) if t.kind == TokKind::Str && t.text.starts_with('"') && t.text.len() >= 2 => {
+                (t.text[1..t.text.len() - 1].to_string(), true)
+            }
+            Some(t) if t.is("<") => match toks.iter().position(|t| t.is(">")) {
+                Some(close) => (toks[1..close].iter().map(|t| t.text.as_str()).collect(), false),
+                None => {
+                    self.error(ErrorKind::BadInclude, file, hash);
+                    return;
+                }
+            },
+            _ => {
+                self.error(ErrorKind::BadInclude, file, hash);
+                return;
+            }
+        };
+        if self.depth >= MAX_INCLUDE_DEPTH {
+            self.error(ErrorKind::IncludeTooDeep, file, hash);
+            return;
+        }
+        let Some((path, text)) = self.find_include(&name, quoted, file) else {
+            self.error(ErrorKind::IncludeNotFound(name), file, hash);
+            return;
+        };
+
+        // The included lines replace the directive line
+        let line_offset = hash.pos + 1 - hash.col;
+        let here = LineOrigin { file, line: hash.line, offset: line_offset, shifts: Vec::new() };
+        self.out.truncate(self.out.rfind('\n').map_or(0, |k| k + 1));
+        self.files.push(SourceFile { name: path, text });
+        let child = self.files.len() - 1;
+        if let Some(last) = self.lines.last_mut() {
+            *last = LineOrigin { file: child, line: 1, offset: 0, shifts: Vec::new() };
+        }
+        self.depth += 1;
+        self.process(child);
+        self.depth -= 1;
+        self.glue = false;
+        // Resume on the rest of the directive line
+        if self.out.is_empty() || self.out.ends_with('\n') {
+            if let Some(last) = self.lines.last_mut() {
+                *last = here;
+            }
+        } else {
+            self.newline(here.file, here.line, here.offset);
+        }
+    }
+
+    // Finds and reads an included file: quoted names are looked up next to the including
+    // file first, then every name in the include paths, then the built-in headers
+    fn find_include(&self, name: &str, quoted: bool, from: usize) -> Option<(String, String)> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        if quoted {
+            dirs.push(Path::new(&self.files[from].name).parent().map_or_else(PathBuf::new, Path::to_path_buf));
+        }
+        dirs.extend(self.include_paths.iter().cloned());
+        let found = dirs.iter().find_map(|dir| {
+            let path = dir.join(name);
+            let text = std::fs::read(&path).ok()?;
+            Some((path.to_string_lossy().into_owned(), String::from_utf8_lossy(&text).into_owned()))
+        });
+        found.or_else(|| {
+            let (_, text) = BUILTIN_HEADERS.iter().find(|(header, _)| *header == name)?;
+            Some((format!("<built-in>/{}", name), text.to_string()))
+        })
+    }
+
+    // Expands the macro invocation starting at `toks[i]` into the output; returns the
+    // index of the first token after it
+    fn expand_at(&mut self, file: usize, toks: &[Tok], i: usize) -> usize {
+        // Finds the extent of a function-like call starting at `from`, if there is one
+        let call_end = |from: usize| -> Option<usize> {
+            let open = skip_space(toks, from);
+            if !toks.get(open).is_some_and(|t| t.is("(")) {
+                return None;
+            }
+            collect_args(toks, open).map(|(_, close)| close)
+        };
+        let function_like = |pp: &Preprocessor, name: &str| pp.macros.get(name).is_some_and(|m| m.params.is_some());
+
+        let mut end = i;
+        if function_like(self, &toks[i].text) {
+            match call_end(i + 1) {
+                Some(close) => end = close,
+                None => {
+                    // Without arguments the name is not a macro call
+                    if toks.get(skip_space(toks, i + 1)).is_some_and(|t| t.is("(")) {
+                        self.error(ErrorKind::BadMacroCall(toks[i].text.clone()), file, &toks[i]);
+                    }
+                    self.emit_source(file, &toks[i]);
+                    return i + 1;
+                }
+            }
+        }
+
+        // Expand; if the result ends in the name of a function-like macro whose arguments
+        // follow in the source, take them in and expand again
+        let expanded = loop {
+            let input: Vec<Tok> = toks[i..=end]
+                .iter()
+                .map(|t| if t.is_space() { Tok { kind: TokKind::Space, text: " ".to_string(), ..t.clone() } } else { t.clone() })
+                .collect();
+            let expanded = self.expand_tokens(input, file);
+            let tail = expanded.iter().rev().find(|t| !t.is_space());
+            match tail {
+                Some(t) if t.kind == TokKind::Ident && !t.hide.contains(&t.text) && function_like(self, &t.text) => {
+                    match call_end(end + 1) {
+                        Some(close) => end = close,
+                        None => break expanded,
+                    }
+                }
+                _ => break expanded,
+            }
+        };
+        self.emit_expansion(&expanded, &toks[i], toks[i].pos..=toks[end].pos);
+        // Lines the call spanned still count, and the rest of its last line keeps its columns
+        for t in &toks[i..=end] {
+            self.newlines_in(file, t);
+        }
+        if toks[end].line != toks[i].line {
+            self.out.extend(std::iter::repeat_n(' ', toks[end].col));
+        }
+        end + 1
+    }
+
+    // Fully macro-expands a token list
+    fn expand_tokens(&mut self, input: Vec<Tok>, file: usize) -> Vec<Tok> {
+        let mut input: VecDeque<Tok> = input.into();
+        let mut out = Vec::new();
+        while let Some(t) = input.pop_front() {
+            if t.kind != TokKind::Ident || t.hide.contains(&t.text) {
+                out.push(t);
+                continue;
+            }
+            // Built-in macros
+            if t.text == "__LINE__" {
+                out.push(Tok::synth(TokKind::Number, t.line.to_string(), &t));
+                continue;
+            }
+            if t.text == "__FILE__" {
+                let name = quote(&self.files[file].name);
+                out.push(Tok::synth(TokKind::Str, name, &t));
+                continue;
+            }
+            let Some(m) = self.macros.get(&t.text).cloned() else {
+                out.push(t);
+                continue;
+            };
+            let mut hide = t.hide.clone();
+            hide.push(t.text.clone());
+
+            let replacement = match &m.params {
+                None => self.substitute(&m, &[], &hide, &t, file),
+                Some(params) => {
+                    let rest: Vec<Tok> = input.iter().cloned().collect();
+                    let open = skip_space(&rest, 0);
+                    let call = if rest.get(open).is_some_and(|t| t.is("(")) { collect_args(&rest, open) } else { None };
+                    let Some((mut args, close)) = call else {
+                        out.push(t);
+                        continue;
+                    };
+                    // A call with no arguments passes one empty argument
+                    if params.is_empty() && args.len() == 1 && args[0].is_empty() {
+                        args.clear();
+                    }
+                    if m.variadic && args.len() >= params.len() {
+                        // Extra arguments all belong to __VA_ARGS__, commas included
+                        let extra = args.split_off(params.len() - 1);
+                        let mut va = Vec::new();
+                        for (n, a) in extra.into_iter().enumerate() {
+                            if n > 0 {
+                                va.push(Tok::synth(TokKind::Punct, ",".to_string(), &t));
+                            }
+                            va.extend(a);
+                        }
+                        args.push(va);
+                    } else if m.variadic && args.len() + 1 == params.len() {
+                        args.push(Vec::new());
+                    }
+                    if args.len() != params.len() {
+                        self.error(ErrorKind::MacroArgCount(t.text.clone()), file, &t);
+                        out.push(t);
+                        continue;
+                    }
+                    input.drain(..=close);
+                    self.substitute(&m, &args, &hide, &t, file)
+                }
+            };
+            for r in replacement.into_iter().rev() {
+                input.push_front(r);
+            }
+        }
+        out
+    }
+
+    // Builds the replacement list of a macro invocation at `at`, with arguments
+    // substituted and `#`/`##` applied
+    fn substitute(&mut self, m: &Macro, args: &[Vec<Tok>], hide: &[String], at: &Tok, file: usize) -> Vec<Tok> {
+        let params = m.params.as_deref().unwrap_or(&[]);
+        let param = |t: &Tok| if t.kind == TokKind::Ident { params.iter().position(|p| *p == t.text) } else { None };
+        let body = &m.body;
+        let mut res: Vec<Tok> = Vec::new();
+        let mut j = 0;
+        while j < body.len() {
+            let b = &body[j];
+            // Stringification: # parameter
+            if m.params.is_some() && b.is("#") {
+                let k = skip_space(body, j + 1);
+                if let Some(p) = body.get(k).and_then(param) {
+                    res.push(stringify(&args[p], at));
+                    j = k + 1;
+                    continue;
+                }
+            }
+            if let Some(p) = param(b) {
+                let next = body.get(skip_space(body, j + 1));
+                let pasted = res.iter().rev().find(|t| !t.is_space()).is_some_and(|t| t.paste) || next.is_some_and(|t| t.paste);
+                if pasted {
+                    // Operands of ## are not expanded
+                    if args[p].is_empty() {
+                        res.push(Tok::synth(TokKind::Space, String::new(), at));
+                    } else {
+                        res.extend(args[p].iter().cloned());
+                    }
+                } else {
+                    let expanded = self.expand_tokens(args[p].clone(), file);
+                    res.extend(expanded);
+                }
+                j += 1;
+                continue;
+            }
+            res.push(b.clone());
+            j += 1;
+        }
+
+        let mut res = paste(res);
+        for r in res.iter_mut() {
+            for h in hide {
+                if !r.hide.contains(h) {
+                    r.hide.push(h.clone());
+                }
+            }
+            r.line = at.line;
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Preprocesses a source, returning its non-blank lines with spacing normalized
+    fn pp(text: &str) -> Vec<String> {
+        let (out, _) = Preprocessor::new().run("t.c", text).unwrap_or_else(|e| panic!("{:?}", e));
+        out.lines().map(|l| l.split_whitespace().collect::<Vec<_>>().join(" ")).filter(|l| !l.is_empty()).collect()
+    }
+
+    #[test]
+    fn object_like_macros() {
+        assert_eq!(pp("#define N 10\n#define M (N + 1)\nx = M;\n"), ["x = (10 + 1);"]);
+        assert_eq!(pp("#define N 1\n#undef N\nN\n"), ["N"]);
+        assert_eq!(pp("#define S \"N\"\n#define N 2\nS N\n"), ["\"N\" 2"]);
+    }
+
+    #[test]
+    fn function_like_macros() {
+        assert_eq!(pp("#define MAX(a, b) ((a) > (b) ? (a) : (b))\nMAX(x, y + 1)\n"), ["((x) > (y + 1) ? (x) : (y + 1))"]);
+        assert_eq!(pp("#define F(a) [a]\nF((1, 2)) F\n"), ["[(1, 2)] F"]);
+        assert_eq!(pp("#define F(a) a\n#define G F\nG(3)\n"), ["3"]);
+        assert_eq!(pp("#define V(fmt, ...) printf(fmt, __VA_ARGS__)\nV(\"%d %d\",1,2)\n"), ["printf(\"%d %d\", 1,2)"]);
+    }
+
+    #[test]
+    fn stringify_and_paste() {
+        assert_eq!(pp("#define S(x) #x\nS(a  +  \"b\")\n"), ["\"a + \\\"b\\\"\""]);
+        assert_eq!(pp("#define CAT(a, b) a ## b\nCAT(x, 1) CAT(+, =)\n"), ["x1 +="]);
+        assert_eq!(pp("#define CAT(a, b) a ## b\n#define x1 99\nCAT(x, 1)\n"), ["99"]);
+    }
+
+    #[test]
+    fn conditionals() {
+        let text = "#define A 3\n#if A * 2 == 6 && !defined(B)\nyes\n#else\nno\n#endif\n";
+        assert_eq!(pp(text), ["yes"]);
+        let text = "#if (1 << 4) % 5 == 2\na\n#elif 7 / 2 == 3\nb\n#else\nc\n#endif\n";
+        assert_eq!(pp(text), ["b"]);
+        let text = "#ifdef UNSET\n#if garbage (\n#endif\na\n#else\nb\n#endif\n";
+        assert_eq!(pp(text), ["b"]);
+        assert_eq!(pp("#if UNDEFINED_NAME\na\n#elif -1 < 0 ? 1 : 0\nb\n#endif\n"), ["b"]);
+    }
+
+    #[test]
+    fn skipped_groups() {
+        // A '#' that does not start its line is not a directive, even in a skipped group
+        assert_eq!(pp("#if 0\nx # endif\n#endif\nkept\n"), ["kept"]);
+        assert_eq!(pp("#if 0\na # if 1\n#else\nb\n#endif\n"), ["b"]);
+        assert_eq!(pp("#if 0\n  # endif\nc\n"), ["c"]);
+    }
+
+    #[test]
+    fn builtin_macros() {
+        let (out, _) = Preprocessor::new().run("dir\\a \"b\".c", "__FILE__ __LINE__\n\n__LINE__\n").unwrap();
+        assert_eq!(out.lines().collect::<Vec<_>>(), [r#""dir\\a \"b\".c" 1"#, "", "3"]);
+        let (out, _) = Preprocessor::new().run("tab\there.c", "__FILE__\n").unwrap();
+        assert_eq!(out.trim_end(), r#""tab\011here.c""#);
+    }
+
+    #[test]
+    fn recursion_is_guarded() {
+        assert_eq!(pp("#define x x + 1\nx\n"), ["x + 1"]);
+        assert_eq!(pp("#define a b\n#define b a\na b\n"), ["a b"]);
+        assert_eq!(pp("#define f(n) f(n + 1)\nf(0)\n"), ["f(0 + 1)"]);
+    }
+
+    #[test]
+    fn columns_map_back_through_expansions() {
+        let (out, lines) = Preprocessor::new().run("t.c", "#define LONGNAME 1\nx = LONGNAME + y;\n").unwrap();
+        let line = out.lines().nth(1).unwrap();
+        let origin = &lines[1];
+        // `y` sits further left in the output than in the source
+        assert_eq!(origin.column(line.find('y').unwrap() + 1), 16);
+        // Tokens of the replacement point at the invocation
+        assert_eq!(origin.column(line.find('1').unwrap() + 1), 5);
+    }
+}
@@ -0,0 +1,73 @@
+// Token definitions shared by the lexer and the parser
+
+// Define Token type for lexical analysis (e.g., operators, keywords)
+pub type Token = i64;
+// Constants for token types, used in parsing source code
+pub const Arrow: Token = 194; // '->'
+pub const Dot: Token = 193; // '.'
+pub const Brak: Token = 192; // '['
+pub const Dec: Token = 191; // '--'
+pub const Inc: Token = 190; // '++'
+pub const Mod: Token = 189; // '%'
+pub const Div: Token = 188; // '/'
+pub const Mul: Token = 187; // '*'
+pub const Sub: Token = 186; // '-'
+pub const Add: Token = 185; // '+'
+pub const Shr: Token = 184; // '>>'
+pub const Shl: Token = 183; // '<<'
+pub const Ge: Token = 182; // '>='
+pub const Le: Token = 181; // '<='
+pub const Gt: Token = 180; // '>'
+pub const Lt: Token = 179; // '<'
+pub const Ne: Token = 178; // '!='
+pub const Eq: Token = 177; // '=='
+pub const And: Token = 176; // '&'
+pub const Xor: Token = 175; // '^'
+pub const Or: Token = 174; // '|'
+pub const Lan: Token = 173; // '&&'
+pub const Lor: Token = 172; // '||'
+pub const Cond: Token = 171; // '?'
+pub const XorAssign: Token = 170; // '^='
+pub const OrAssign: Token = 169; // '|='
+pub const AndAssign: Token = 168; // '&='
+pub const ShrAssign: Token = 167; // '>>='
+pub const ShlAssign: Token = 166; // '<<='
+pub const ModAssign: Token = 165; // '%='
+pub const DivAssign: Token = 164; // '/='
+pub const MulAssign: Token = 163; // '*='
+pub const SubAssign: Token = 162; // '-='
+pub const AddAssign: Token = 161; // '+='
+pub const Assign: Token = 160; // '='
+pub const Void: Token = 159; // 'void'
+pub const Double: Token = 158; // 'double'
+pub const Float: Token = 157; // 'float'
+pub const Signed: Token = 156; // 'signed'
+pub const Unsigned: Token = 155; // 'unsigned'
+pub const Long: Token = 154; // 'long'
+pub const Short: Token = 153; // 'short'
+pub const Typedef: Token = 152; // 'typedef', also the class of typedef names
+pub const Union: Token = 151; // 'union'
+pub const Struct: Token = 150; // 'struct'
+pub const Goto: Token = 149; // 'goto'
+pub const Default: Token = 148; // 'default'
+pub const Case: Token = 147; // 'case'
+pub const Switch: Token = 146; // 'switch'
+pub const Continue: Token = 145; // 'continue'
+pub const Break: Token = 144; // 'break'
+pub const Do: Token = 143; // 'do'
+pub const For: Token = 142; // 'for'
+pub const While: Token = 141; // 'while'
+pub const Sizeof: Token = 140; // 'sizeof'
+pub const Return: Token = 139; // 'return'
+pub const Int: Token = 138; // 'int'
+pub const If: Token = 137; // 'if'
+pub const Enum: Token = 136; // 'enum'
+pub const Else: Token = 135; // 'else'
+pub const Char: Token = 134; // 'char'
+pub const Id: Token = 133; // Identifier (variable/function name)
+pub const Loc: Token = 132; // Local variable
+pub const Glo: Token = 131; // Global variable
+pub const Sys: Token = 130; // System call/function
+pub const Fun: Token = 129; // Function
+pub const Num: Token = 128; // Numeric literal
+
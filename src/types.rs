@@ -0,0 +1,370 @@
+// Type table: every type the compiler has seen, referred to by index
+
+use crate::opcode::extend;
+
+// Index of a type in the type table
+pub type Ty = usize;
+
+// Types every table starts with
+pub const CHAR: Ty = 0; // Character type (signed)
+pub const INT: Ty = 1; // Integer type
+pub const UCHAR: Ty = 2; // unsigned char
+pub const SHORT: Ty = 3; // short
+pub const USHORT: Ty = 4; // unsigned short
+pub const UINT: Ty = 5; // unsigned int
+pub const LONG: Ty = 6; // long (and long long)
+pub const ULONG: Ty = 7; // unsigned long (and unsigned long long)
+pub const FLOAT: Ty = 8; // float
+pub const DOUBLE: Ty = 9; // double (and long double)
+pub const VOID: Ty = 10; // void: no value
+
+// Size of a machine word in the virtual machine: a long, a pointer or a stack slot
+pub const WORD_SIZE: i64 = std::mem::size_of::<i64>() as i64;
+
+// Largest size of a type in bytes, leaving room to round sizes up without overflow
+pub const MAX_SIZE: i64 = i64::MAX / 2;
+
+// What a type is made of
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeKind {
+    Char, // Signed byte
+    UChar, // Unsigned byte
+    Short, // Signed 16 bits
+    UShort, // Unsigned 16 bits
+    Int, // Signed 32 bits
+    UInt, // Unsigned 32 bits
+    Long, // Signed machine word
+    ULong, // Unsigned machine word
+    Float, // Single-precision floating point
+    Double, // Double-precision floating point
+    Void, // No value; an incomplete type
+    Ptr(Ty), // Pointer to another type
+    Array(Ty, i64), // Fixed number of elements of another type (0 while the size is unknown)
+    Record(usize), // Struct or union, indexing the record table
+    Func(usize), // Function, indexing the signature table
+}
+
+// A member of a struct or union
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub name: usize, // Symbol of the member's name
+    pub ty: Ty, // Type of the member
+    pub offset: i64, // Byte offset from the start of the record
+}
+
+// Layout of a struct or union
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub union: bool, // All members start at offset 0
+    pub tag: Option<usize>, // Symbol of the tag, if the record has one
+    pub fields: Vec<Field>, // Members in declaration order
+    pub size: i64, // Size in bytes, including trailing padding
+    pub align: i64, // Alignment in bytes
+    pub complete: bool, // The member list has been seen
+}
+
+// Return and parameter types of a function type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub ret: Ty, // Return type
+    pub params: Option<Vec<Ty>>, // Parameter types, after arrays and functions are adjusted to pointers; None for `()`
+    pub variadic: bool, // The parameter list ends with '...'
+}
+
+// The type table
+#[derive(Clone, Debug)]
+pub struct Types {
+    kinds: Vec<TypeKind>, // Every type, each kind only once
+    records: Vec<Record>, // Every struct and union
+    sigs: Vec<Signature>, // Every function signature, each only once
+}
+
+impl Default for Types {
+    fn default() -> Self {
+        Types::new()
+    }
+}
+
+impl Types {
+    // Creates a table holding only the basic types
+    pub fn new() -> Types {
+        let kinds = vec![
+            TypeKind::Char,
+            TypeKind::Int,
+            TypeKind::UChar,
+            TypeKind::Short,
+            TypeKind::UShort,
+            TypeKind::UInt,
+            TypeKind::Long,
+            TypeKind::ULong,
+            TypeKind::Float,
+            TypeKind::Double,
+            TypeKind::Void,
+        ];
+        Types { kinds, records: Vec::new(), sigs: Vec::new() }
+    }
+
+    // What a type is made of
+    pub fn kind(&self, t: Ty) -> TypeKind {
+        self.kinds[t]
+    }
+
+    // Finds or adds a type of the given kind
+    fn intern(&mut self, kind: TypeKind) -> Ty {
+        match self.kinds.iter().position(|k| *k == kind) {
+            Some(t) => t,
+            None => {
+                self.kinds.push(kind);
+                self.kinds.len() - 1
+            }
+        }
+    }
+
+    // Pointer to `t`
+    pub fn ptr(&mut self, t: Ty) -> Ty {
+        self.intern(TypeKind::Ptr(t))
+    }
+
+    // Returns true if an array of `n` elements of type `t` is no larger than MAX_SIZE
+    pub fn fits(&self, t: Ty, n: i64) -> bool {
+        self.size(t).checked_mul(n).is_some_and(|size| size <= MAX_SIZE)
+    }
+
+    // Array of `n` elements of type `t`
+    pub fn array(&mut self, t: Ty, n: i64) -> Ty {
+        self.intern(TypeKind::Array(t, n))
+    }
+
+    // Function returning `ret` with the given parameters
+    pub fn func(&mut self, ret: Ty, params: Option<Vec<Ty>>, variadic: bool) -> Ty {
+        let sig = Signature { ret, params, variadic };
+        let s = match self.sigs.iter().position(|s| *s == sig) {
+            Some(s) => s,
+            None => {
+                self.sigs.push(sig);
+                self.sigs.len() - 1
+            }
+        };
+        self.intern(TypeKind::Func(s))
+    }
+
+    // Type an array or function turns into when its value is used: a pointer to its
+    // first element, or to the function
+    pub fn decay(&mut self, t: Ty) -> Ty {
+        match self.kinds[t] {
+            TypeKind::Array(elem, _) => self.ptr(elem),
+            TypeKind::Func(_) => self.ptr(t),
+            _ => t,
+        }
+    }
+
+    // Type `t` points to, if it is a pointer (or the element type of an array)
+    pub fn deref(&self, t: Ty) -> Option<Ty> {
+        match self.kinds[t] {
+            TypeKind::Ptr(to) | TypeKind::Array(to, _) => Some(to),
+            _ => None,
+        }
+    }
+
+    // Returns true for array types
+    pub fn is_array(&self, t: Ty) -> bool {
+        matches!(self.kinds[t], TypeKind::Array(..))
+    }
+
+    // Returns true for pointer types
+    pub fn is_ptr(&self, t: Ty) -> bool {
+        self.deref(t).is_some()
+    }
+
+    // Returns true for the integer types
+    pub fn is_integer(&self, t: Ty) -> bool {
+        t <= ULONG
+    }
+
+    // Returns true for float and double
+    pub fn is_float(&self, t: Ty) -> bool {
+        matches!(t, FLOAT | DOUBLE)
+    }
+
+    // Returns true for the integer and floating-point types
+    pub fn is_arithmetic(&self, t: Ty) -> bool {
+        t <= DOUBLE
+    }
+
+    // Returns true for the unsigned integer types
+    pub fn is_unsigned(&self, t: Ty) -> bool {
+        matches!(t, UCHAR | USHORT | UINT | ULONG)
+    }
+
+    // Type an integer is promoted to in arithmetic: int for the types smaller than it,
+    // which it can hold every value of
+    pub fn promote(&self, t: Ty) -> Ty {
+        match t {
+            CHAR | UCHAR | SHORT | USHORT => INT,
+            _ => t,
+        }
+    }
+
+    // Type both operands of a binary operator are converted to (the usual arithmetic
+    // conversions): double or float if either operand is one, else the larger of the
+    // promoted types, unsigned if either of two of the same size is. Pointers count as
+    // unsigned long.
+    pub fn common(&self, a: Ty, b: Ty) -> Ty {
+        if a == DOUBLE || b == DOUBLE {
+            return DOUBLE;
+        } else if a == FLOAT || b == FLOAT {
+            return FLOAT;
+        }
+        let [a, b] = [a, b].map(|t| if self.is_integer(t) { self.promote(t) } else { ULONG });
+        if a == ULONG || b == ULONG {
+            ULONG
+        } else if a == LONG || b == LONG {
+            LONG
+        } else if a == UINT || b == UINT {
+            UINT
+        } else {
+            INT
+        }
+    }
+
+    // Value `v` converted to integer type `t`: wrapped into its range
+    pub fn wrap(&self, t: Ty, v: i64) -> i64 {
+        if self.is_integer(t) {
+            extend(v, self.size(t), self.is_unsigned(t))
+        } else {
+            v
+        }
+    }
+
+    // Returns true for function types
+    pub fn is_func(&self, t: Ty) -> bool {
+        matches!(self.kinds[t], TypeKind::Func(_))
+    }
+
+    // Signature of a function type
+    pub fn signature(&self, t: Ty) -> Option<&Signature> {
+        match self.kinds[t] {
+            TypeKind::Func(s) => Some(&self.sigs[s]),
+            _ => None,
+        }
+    }
+
+    // Returns true if a value of type `a` can stand for one of type `b`: the same type,
+    // pointers to compatible types, or functions with compatible returns and parameters
+    pub fn compatible(&self, a: Ty, b: Ty) -> bool {
+        if a == b {
+            return true;
+        }
+        match (self.kinds[a], self.kinds[b]) {
+            (TypeKind::Ptr(x), TypeKind::Ptr(y)) => self.compatible(x, y),
+            (TypeKind::Func(x), TypeKind::Func(y)) => {
+                let (x, y) = (&self.sigs[x], &self.sigs[y]);
+                self.compatible(x.ret, y.ret) && self.params_agree(x.params.as_deref(), x.variadic, y.params.as_deref(), y.variadic)
+            }
+            _ => false,
+        }
+    }
+
+    // Returns true if two parameter lists (None for `()`) can belong to the same function.
+    // A `()` list agrees with any fixed list of parameters that the default argument
+    // promotions leave unchanged, since that is how its arguments are passed.
+    pub fn params_agree(&self, a: Option<&[Ty]>, a_variadic: bool, b: Option<&[Ty]>, b_variadic: bool) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                a_variadic == b_variadic && a.len() == b.len() && a.iter().zip(b).all(|(&x, &y)| self.compatible(x, y))
+            }
+            (Some(p), None) | (None, Some(p)) => {
+                !a_variadic && !b_variadic && p.iter().all(|&t| !matches!(t, CHAR | UCHAR | SHORT | USHORT | FLOAT))
+            }
+            (None, None) => true,
+        }
+    }
+
+    // Returns true for structs and unions
+    pub fn is_record(&self, t: Ty) -> bool {
+        matches!(self.kinds[t], TypeKind::Record(_))
+    }
+
+    // Size of a value of type `t` in bytes (1 for void and for a function, which is what
+    // pointer arithmetic on their pointers steps over)
+    pub fn size(&self, t: Ty) -> i64 {
+        match self.kinds[t] {
+            TypeKind::Char | TypeKind::UChar | TypeKind::Void | TypeKind::Func(_) => 1,
+            TypeKind::Short | TypeKind::UShort => 2,
+            TypeKind::Int | TypeKind::UInt | TypeKind::Float => 4,
+            TypeKind::Long | TypeKind::ULong | TypeKind::Double | TypeKind::Ptr(_) => WORD_SIZE,
+            TypeKind::Array(elem, n) => self.size(elem) * n, // Kept within MAX_SIZE by fits()
+            TypeKind::Record(r) => self.records[r].size,
+        }
+    }
+
+    // Alignment of a value of type `t` in bytes
+    pub fn align(&self, t: Ty) -> i64 {
+        match self.kinds[t] {
+            TypeKind::Record(r) => self.records[r].align,
+            TypeKind::Array(elem, _) => self.align(elem),
+            _ => self.size(t),
+        }
+    }
+
+    // Returns false for structs and unions whose members have not been seen yet, for
+    // arrays of unknown size, for void and for functions, which are not objects
+    pub fn is_complete(&self, t: Ty) -> bool {
+        match self.kinds[t] {
+            TypeKind::Array(elem, n) => n > 0 && self.is_complete(elem),
+            TypeKind::Record(r) => self.records[r].complete,
+            TypeKind::Void | TypeKind::Func(_) => false,
+            _ => true,
+        }
+    }
+
+    // Layout of a struct or union type
+    pub fn record(&self, t: Ty) -> Option<&Record> {
+        match self.kinds[t] {
+            TypeKind::Record(r) => Some(&self.records[r]),
+            _ => None,
+        }
+    }
+
+    // Adds a new struct or union type whose members are not known yet
+    pub fn new_record(&mut self, union: bool, tag: Option<usize>) -> Ty {
+        self.records.push(Record { union, tag, fields: Vec::new(), size: 0, align: 1, complete: false });
+        let r = self.records.len() - 1;
+        self.kinds.push(TypeKind::Record(r));
+        self.kinds.len() - 1
+    }
+
+    // Lays out the members of a struct or union: each member is placed at the next
+    // offset aligned for its type (all at 0 in a union), and the size is padded to a
+    // multiple of the strictest alignment. Returns false if the size would exceed
+    // MAX_SIZE, leaving the type incomplete.
+    pub fn complete_record(&mut self, t: Ty, members: Vec<(usize, Ty)>) -> bool {
+        let TypeKind::Record(r) = self.kinds[t] else {
+            return false;
+        };
+        let union = self.records[r].union;
+        let (mut size, mut align) = (0, 1);
+        let mut fields = Vec::with_capacity(members.len());
+        for (name, ty) in members {
+            let a = self.align(ty);
+            let offset = if union { 0 } else { (size + a - 1) / a * a };
+            size = if union { size.max(self.size(ty)) } else { offset + self.size(ty) };
+            if size > MAX_SIZE {
+                return false;
+            }
+            align = align.max(a);
+            fields.push(Field { name, ty, offset });
+        }
+        let record = &mut self.records[r];
+        record.fields = fields;
+        record.align = align;
+        record.size = (size + align - 1) / align * align;
+        record.complete = true;
+        true
+    }
+
+    // Member `name` of a struct or union type
+    pub fn field(&self, t: Ty, name: usize) -> Option<&Field> {
+        self.record(t)?.fields.iter().find(|f| f.name == name)
+    }
+}
@@ -0,0 +1,337 @@
+// Stack-based virtual machine that executes compiled programs
+
+use std::ffi::{CStr, CString};
+
+use libc::{c_char, c_int, c_void};
+
+use crate::compiler::{Program, POOL_SIZE};
+use crate::opcode::*;
+
+// Virtual machine state: the loaded program, its stack and registers
+pub struct Vm {
+    pub debug: bool, // Print each instruction as it executes (-d)
+    code: Box<[i64]>, // Code segment
+    _data: Box<[i64]>, // Data segment, kept alive for the addresses baked into the code
+    stack: Box<[i64]>, // Runtime stack, growing downwards
+    main: usize, // Code index of main()
+    exit: usize, // Code index of the exit stub
+    args: Vec<CString>, // Argument strings passed to main()
+    argv: Vec<i64>, // Argument vector passed to main()
+    pc: usize, // Program counter
+    sp: i64, // Stack pointer
+    bp: i64, // Base pointer
+    a: i64, // Accumulator
+    cycle: i64, // Instruction cycle counter
+}
+
+// Stack words kept free beyond each frame for temporaries and call arguments
+const STACK_RESERVE: i64 = 1024;
+
+// Loads an int from a host address
+unsafe fn load(addr: i64) -> i64 {
+    *(addr as *const i64)
+}
+
+// Stores an int at a host address
+unsafe fn store(addr: i64, v: i64) {
+    *(addr as *mut i64) = v;
+}
+
+impl Vm {
+    // Creates a virtual machine with a default-sized stack for a compiled program
+    pub fn new(program: Program) -> Vm {
+        Vm {
+            debug: false,
+            code: program.code,
+            _data: program.data,
+            stack: vec![0; POOL_SIZE / std::mem::size_of::<i64>()].into_boxed_slice(),
+            main: program.main,
+            exit: program.exit,
+            args: Vec::new(),
+            argv: Vec::new(),
+            pc: 0,
+            sp: 0,
+            bp: 0,
+            a: 0,
+            cycle: 0,
+        }
+    }
+
+    // Pushes a word onto the stack
+    fn push(&mut self, v: i64) {
+        self.sp -= 8;
+        unsafe { store(self.sp, v) }
+    }
+
+    // Pops a word off the stack
+    fn pop(&mut self) -> i64 {
+        let v = unsafe { load(self.sp) };
+        self.sp += 8;
+        v
+    }
+
+    // Reads the stack word `i` slots above the stack pointer
+    fn arg(&self, i: i64) -> i64 {
+        unsafe { load(self.sp + i * 8) }
+    }
+
+    // Reads the operand word following the current instruction
+    fn operand(&mut self) -> i64 {
+        let v = self.code[self.pc];
+        self.pc += 1;
+        v
+    }
+
+    // Runs printf with the `n` arguments of the current call. The format is printed one
+    // conversion at a time, so that each value is passed as the type its conversion
+    // expects: doubles do not travel in the same registers as integers.
+    fn printf(&self, n: i64) -> i64 {
+        let mut next = n; // Stack index of the argument after the last one taken
+        let mut arg = || {
+            next -= 1;
+            self.arg(next)
+        };
+        let fmt = unsafe { CStr::from_ptr(arg() as *const c_char) }.to_bytes();
+        let mut total = 0;
+        let mut i = 0;
+        while i < fmt.len() {
+            // A piece of the format: text up to and including the next conversion
+            let start = i;
+            let mut conv = None;
+            while i < fmt.len() && conv.is_none() {
+                if fmt[i] == b'%' && fmt.get(i + 1) == Some(&b'%') {
+                    i += 2;
+                } else if fmt[i] == b'%' {
+                    // Flags, width, precision and length modifier, then the conversion
+                    i += 1;
+                    while i < fmt.len() && b"-+ #0123456789.*hlLqjzt".contains(&fmt[i]) {
+                        i += 1;
+                    }
+                    conv = fmt.get(i).copied();
+                    i += 1;
+                } else {
+                    i += 1;
+                }
+            }
+            let piece = &fmt[start..i.min(fmt.len())];
+            let Ok(piece) = CString::new(piece) else { break };
+            let p = piece.as_ptr();
+            // Widths and precisions given as '*' come first
+            let stars = piece.as_bytes().iter().filter(|&&c| c == b'*').count();
+            let stars: Vec<c_int> = (0..stars).map(|_| arg() as c_int).collect();
+            let printed = unsafe {
+                match conv {
+                    None => libc::printf(p),
+                    Some(c) if b"fFeEgGaA".contains(&c) => {
+                        let v = float(arg());
+                        match stars[..] {
+                            [] => libc::printf(p, v),
+                            [w] => libc::printf(p, w, v),
+                            [w, q, ..] => libc::printf(p, w, q, v),
+                        }
+                    }
+                    Some(_) => {
+                        let v = arg();
+                        match stars[..] {
+                            [] => libc::printf(p, v),
+                            [w] => libc::printf(p, w, v),
+                            [w, q, ..] => libc::printf(p, w, q, v),
+                        }
+                    }
+                }
+            };
+            total += printed as i64;
+        }
+        total
+    }
+
+    // Runs main() with the given command-line arguments and returns its exit code
+    pub fn run(&mut self, args: &[String]) -> i64 {
+        // Build argc/argv for main()
+        self.args = args.iter().map(|s| CString::new(s.as_str()).unwrap_or_default()).collect();
+        self.argv = self.args.iter().map(|s| s.as_ptr() as i64).collect();
+        self.argv.push(0);
+
+        // Initialize runtime stack
+        self.sp = self.stack.as_mut_ptr() as i64 + (self.stack.len() * 8) as i64; // Set stack top
+        self.bp = self.sp;
+        self.push(self.args.len() as i64); // Push argument count
+        self.push(self.argv.as_ptr() as i64); // Push argument vector
+        self.push(self.exit as i64); // Push return address
+        self.pc = self.main;
+        self.cycle = 0;
+
+        // Execute generated code
+        loop {
+            let i = self.code[self.pc]; // Fetch instruction
+            self.pc += 1;
+            self.cycle += 1;
+
+            // Debug output
+            if self.debug {
+                unsafe {
+                    libc::printf(c"%d> %.4s".as_ptr(), self.cycle, op_name(i).as_ptr() as *const c_char);
+                    if has_operand(i) {
+                        libc::printf(c" %d\n".as_ptr(), self.code[self.pc]);
+                    } else {
+                        libc::printf(c"\n".as_ptr());
+                    }
+                }
+            }
+
+            // Execute instructions
+            match i {
+                LEA => self.a = self.bp + self.operand() * 8, // Load effective address
+                IMM => self.a = self.operand(), // Load immediate value
+                JMP => self.pc = self.code[self.pc] as usize, // Jump
+                JSR => {
+                    self.push(self.pc as i64 + 1); // Push return address
+                    self.pc = self.code[self.pc] as usize; // Jump to subroutine
+                }
+                JSRI => {
+                    // The operand is the argument count; the address was pushed before them
+                    let target = self.arg(self.code[self.pc]);
+                    self.push(self.pc as i64 + 1); // Push return address
+                    self.pc = target as usize; // Jump to subroutine
+                }
+                BZ => self.pc = if self.a != 0 { self.pc + 1 } else { self.code[self.pc] as usize }, // Branch if zero
+                BNZ => self.pc = if self.a != 0 { self.code[self.pc] as usize } else { self.pc + 1 }, // Branch if non-zero
+                JTAB => {
+                    // The operand points at [lowest value, entry count, default target, targets...]
+                    let t = self.operand();
+                    let (low, n, default) = unsafe { (load(t), load(t + 8), load(t + 16)) };
+                    let i = self.a.wrapping_sub(low);
+                    self.pc = if (0..n).contains(&i) { unsafe { load(t + 24 + i * 8) } } else { default } as usize;
+                }
+                ENT => {
+                    // The frame, and the temporaries and arguments pushed while it is
+                    // active, must fit in what is left of the stack
+                    let frame = self.code[self.pc] + STACK_RESERVE;
+                    if frame > (self.sp - self.stack.as_ptr() as i64) / 8 - 1 {
+                        println!("stack overflow! cycle = {}", self.cycle);
+                        return -1;
+                    }
+                    self.push(self.bp); // Save base pointer
+                    self.bp = self.sp;
+                    self.sp -= self.operand() * 8; // Allocate stack frame
+                }
+                ADJ => self.sp += self.operand() * 8, // Adjust stack
+                LEV => {
+                    self.sp = self.bp; // Restore stack pointer
+                    self.bp = self.pop(); // Restore base pointer
+                    self.pc = self.pop() as usize; // Return
+                }
+                LI => self.a = unsafe { load(self.a) }, // Load integer
+                LC => self.a = unsafe { *(self.a as *const c_char) } as i64, // Load char
+                SI => {
+                    let addr = self.pop();
+                    unsafe { store(addr, self.a) } // Store integer
+                }
+                LS => self.a = unsafe { *(self.a as *const i16) } as i64, // Load short
+                LW => self.a = unsafe { *(self.a as *const i32) } as i64, // Load 32-bit word
+                LCU => self.a = unsafe { *(self.a as *const u8) } as i64, // Load unsigned char
+                LSU => self.a = unsafe { *(self.a as *const u16) } as i64, // Load unsigned short
+                LWU => self.a = unsafe { *(self.a as *const u32) } as i64, // Load unsigned 32-bit word
+                SC => {
+                    let addr = self.pop();
+                    unsafe { *(addr as *mut c_char) = self.a as c_char } // Store char
+                }
+                SS => {
+                    let addr = self.pop();
+                    unsafe { *(addr as *mut i16) = self.a as i16 } // Store short
+                }
+                SW => {
+                    let addr = self.pop();
+                    unsafe { *(addr as *mut i32) = self.a as i32 } // Store 32-bit word
+                }
+                LF => self.a = bits(unsafe { *(self.a as *const f32) } as f64), // Load float
+                SF => {
+                    let addr = self.pop();
+                    unsafe { *(addr as *mut f32) = float(self.a) as f32 } // Store float
+                }
+                SWAP => {
+                    let t = self.arg(0);
+                    unsafe { store(self.sp, self.a) } // Exchange with the top of the stack
+                    self.a = t;
+                }
+                SEXT => self.a = extend(self.a, self.operand(), false), // Sign-extend
+                ZEXT => self.a = extend(self.a, self.operand(), true), // Zero-extend
+                PSH => self.push(self.a), // Push accumulator
+                OR => self.a |= self.pop(), // Bitwise OR
+                XOR => self.a ^= self.pop(), // Bitwise XOR
+                AND => self.a &= self.pop(), // Bitwise AND
+                EQ => self.a = (self.pop() == self.a) as i64, // Equality
+                NE => self.a = (self.pop() != self.a) as i64, // Inequality
+                LT => self.a = (self.pop() < self.a) as i64, // Less than
+                GT => self.a = (self.pop() > self.a) as i64, // Greater than
+                LE => self.a = (self.pop() <= self.a) as i64, // Less or equal
+                GE => self.a = (self.pop() >= self.a) as i64, // Greater or equal
+                SHL => self.a = self.pop().wrapping_shl(self.a as u32), // Left shift
+                SHR => self.a = self.pop().wrapping_shr(self.a as u32), // Right shift
+                ADD => self.a = self.pop().wrapping_add(self.a), // Addition
+                SUB => self.a = self.pop().wrapping_sub(self.a), // Subtraction
+                MUL => self.a = self.pop().wrapping_mul(self.a), // Multiplication
+                DIV | MOD => {
+                    if self.a == 0 {
+                        println!("division by zero! cycle = {}", self.cycle);
+                        return -1;
+                    }
+                    let b = self.pop();
+                    self.a = if i == DIV { b.wrapping_div(self.a) } else { b.wrapping_rem(self.a) }; // Division or modulo
+                }
+                DIVU | MODU => {
+                    // Both operands are taken as unsigned values of the operand's width
+                    let n = self.operand();
+                    let b = extend(self.pop(), n, true) as u64;
+                    let a = extend(self.a, n, true) as u64;
+                    if a == 0 {
+                        println!("division by zero! cycle = {}", self.cycle);
+                        return -1;
+                    }
+                    self.a = if i == DIVU { b / a } else { b % a } as i64;
+                }
+                SHRU => self.a = (self.pop() as u64).wrapping_shr(self.a as u32) as i64, // Logical shift right
+                LTU => self.a = ((self.pop() as u64) < self.a as u64) as i64, // Unsigned less than
+                GTU => self.a = (self.pop() as u64 > self.a as u64) as i64, // Unsigned greater than
+                LEU => self.a = (self.pop() as u64 <= self.a as u64) as i64, // Unsigned less or equal
+                GEU => self.a = (self.pop() as u64 >= self.a as u64) as i64, // Unsigned greater or equal
+                ITOF => self.a = bits(self.a as f64), // Signed integer to floating point
+                UTOF => self.a = bits(self.a as u64 as f64), // Unsigned integer to floating point
+                FTOI => self.a = float(self.a) as i64, // Floating point to signed integer
+                FTOU => self.a = float(self.a) as u64 as i64, // Floating point to unsigned integer
+                DTOF => self.a = bits(float(self.a) as f32 as f64), // Round to float precision
+                FADD => self.a = bits(float(self.pop()) + float(self.a)), // Floating-point addition
+                FSUB => self.a = bits(float(self.pop()) - float(self.a)), // Floating-point subtraction
+                FMUL => self.a = bits(float(self.pop()) * float(self.a)), // Floating-point multiplication
+                FDIV => self.a = bits(float(self.pop()) / float(self.a)), // Floating-point division
+                FEQ => self.a = (float(self.pop()) == float(self.a)) as i64, // Floating-point equal
+                FNE => self.a = (float(self.pop()) != float(self.a)) as i64, // Floating-point not equal
+                FLT => self.a = (float(self.pop()) < float(self.a)) as i64, // Floating-point less than
+                FGT => self.a = (float(self.pop()) > float(self.a)) as i64, // Floating-point greater than
+                FLE => self.a = (float(self.pop()) <= float(self.a)) as i64, // Floating-point less or equal
+                FGE => self.a = (float(self.pop()) >= float(self.a)) as i64, // Floating-point greater or equal
+                OPEN => self.a = unsafe { libc::open(self.arg(1) as *const c_char, self.arg(0) as c_int) } as i64, // File open
+                READ => {
+                    self.a = unsafe { libc::read(self.arg(2) as c_int, self.arg(1) as *mut c_void, self.arg(0) as usize) } as i64
+                } // File read
+                CLOS => self.a = unsafe { libc::close(self.arg(0) as c_int) } as i64, // File close
+                PRTF => self.a = self.printf(self.code[self.pc + 1]), // Printf, given the argument count
+                MALC => self.a = unsafe { libc::malloc(self.arg(0) as usize) } as i64, // Memory allocation
+                FREE => unsafe { libc::free(self.arg(0) as *mut c_void) }, // Free memory
+                MSET => {
+                    self.a = unsafe { libc::memset(self.arg(2) as *mut c_void, self.arg(1) as c_int, self.arg(0) as usize) } as i64
+                } // Memory set
+                MCMP => {
+                    self.a = unsafe {
+                        libc::memcmp(self.arg(2) as *const c_void, self.arg(1) as *const c_void, self.arg(0) as usize)
+                    } as i64
+                } // Memory compare
+                EXIT => return self.arg(0), // Exit program
+                _ => {
+                    println!("unknown instruction = {}! cycle = {}", i, self.cycle);
+                    return -1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+// The c4 command line: options and how diagnostics are printed
+
+mod common;
+
+use common::c4;
+
+const USAGE: &str = "usage: c4 [-s] [-d] [-I dir] [-D name[=value]] [--max-errors=N] [--diagnostics-format=text|json] file ...\n";
+
+#[test]
+fn help_and_unknown_options() {
+    assert_eq!(c4(&["--help"], ""), (USAGE.to_string(), 0));
+    assert_eq!(c4(&["-h"], ""), (USAGE.to_string(), 0));
+    assert_eq!(c4(&["--verbose"], ""), (format!("unknown option: --verbose\n{}", USAGE), 255));
+}
+
+#[test]
+fn errors_show_the_source_line_with_a_caret() {
+    let (out, code) = c4(&[], "int main() {\n    int count;\n    return count + missing;\n}\n");
+    let expected = "t.c:3:20: error: undefined variable\n \
+                    3 |     return count + missing;\n   \
+                    |                    ^~~~~~~\n";
+    assert_eq!((out.as_str(), code), (expected, 255));
+}
+
+#[test]
+fn notes_point_at_related_lines() {
+    let (out, _) = c4(&[], "int x;\nchar x;\nint main() { return 0; }\n");
+    let expected = "t.c:2:6: error: duplicate global definition\n \
+                    2 | char x;\n   \
+                    |      ^\n\
+                    t.c:1:5: note: previous definition was here\n \
+                    1 | int x;\n   \
+                    |     ^\n";
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn json_diagnostics_in_source_order() {
+    let source = "int f(int *p) { return 0; }\nint main() {\n    int x;\n    x = y;\n    f(3);\n    return q;\n}\n";
+    let (out, code) = c4(&["--diagnostics-format=json"], source);
+    let expected = [
+        r#"{"file":"t.c","line":4,"column":9,"severity":"error","code":"undefined-variable","message":"undefined variable","notes":[]}"#,
+        r#"{"file":"t.c","line":5,"column":7,"severity":"warning","code":"int-to-pointer","message":"passing argument 1 makes pointer from integer without a cast","notes":[]}"#,
+        r#"{"file":"t.c","line":6,"column":12,"severity":"error","code":"undefined-variable","message":"undefined variable","notes":[]}"#,
+    ];
+    assert_eq!((out.lines().collect::<Vec<_>>(), code), (expected.to_vec(), 255));
+    // Text output keeps the same order
+    let (out, _) = c4(&[], source);
+    let heads: Vec<_> = out.lines().filter(|l| l.starts_with("t.c:")).collect();
+    assert_eq!(heads, ["t.c:4:9: error: undefined variable", "t.c:5:7: warning: passing argument 1 makes pointer from integer without a cast", "t.c:6:12: error: undefined variable"]);
+}
+
+#[test]
+fn json_notes() {
+    let (out, _) = c4(&["--diagnostics-format=json"], "int x;\nchar x;\nint main() { return 0; }\n");
+    let expected = r#"{"file":"t.c","line":2,"column":6,"severity":"error","code":"duplicate-definition","message":"duplicate global definition","notes":[{"file":"t.c","line":1,"column":5,"message":"previous definition was here"}]}"#;
+    assert_eq!(out.trim_end(), expected);
+}
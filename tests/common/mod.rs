@@ -0,0 +1,61 @@
+// Helpers shared by the integration tests
+#![allow(dead_code)] // Each test file uses only some of them
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use c4::{Compiler, ErrorKind, Vm};
+
+// Compiles a program expected to fail, returning the kind and line of each error
+pub fn errors(source: &str) -> Vec<(ErrorKind, usize)> {
+    match Compiler::new().compile(source) {
+        Ok(_) => panic!("compiled without errors"),
+        Err(errors) => errors.into_iter().map(|e| (e.kind, e.span.line)).collect(),
+    }
+}
+
+// Compiles a program expected to succeed, returning the kind and line of each warning
+pub fn warnings(source: &str) -> Vec<(ErrorKind, usize)> {
+    let mut compiler = Compiler::new();
+    if let Err(errors) = compiler.compile(source) {
+        panic!("{:?}", errors);
+    }
+    compiler.warnings().iter().map(|e| (e.kind.clone(), e.span.line)).collect()
+}
+
+// Compiles and runs a program with the given compiler, returning its exit code
+pub fn run(compiler: &mut Compiler, source: &str) -> i64 {
+    let program = compiler.compile(source).unwrap_or_else(|errors| panic!("{:?}", errors));
+    Vm::new(program).run(&["prog".to_string()])
+}
+
+// Compiles and runs a program with a new compiler, returning its exit code
+pub fn exit_code(source: &str) -> i64 {
+    run(&mut Compiler::new(), source)
+}
+
+// Writes a source file named t.c into a directory of its own, returning the directory
+pub fn source_dir(source: &str) -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNT.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("c4-test-{}-{}", std::process::id(), n));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("t.c"), source).unwrap();
+    dir
+}
+
+// Runs the c4 binary with the given options on a program saved as t.c, returning what
+// it printed and its exit code
+pub fn c4(options: &[&str], source: &str) -> (String, i32) {
+    let dir = source_dir(source);
+    let output = Command::new(env!("CARGO_BIN_EXE_c4")).args(options).arg("t.c").current_dir(&dir).output().unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+    (String::from_utf8_lossy(&output.stdout).into_owned(), output.status.code().unwrap_or(-1))
+}
+
+// Compiles and runs a program with the c4 binary, returning what it printed and its exit code
+pub fn output(source: &str) -> (String, i32) {
+    c4(&[], source)
+}
@@ -0,0 +1,110 @@
+// Diagnostics: what is reported for bad programs, and where
+
+mod common;
+
+use c4::{Compiler, ErrorKind};
+use common::{c4, errors, warnings};
+
+const SEVERAL_ERRORS: &str = "int main() {\n\
+                               int x;\n\
+                               x = y;\n\
+                               x = ;\n\
+                               return z;\n\
+                               }\n\
+                               int f() { return q; }\n";
+
+#[test]
+fn every_error_is_reported() {
+    let expected = [
+        (ErrorKind::UndefinedVariable, 3),
+        (ErrorKind::BadExpression, 4),
+        (ErrorKind::UndefinedVariable, 5),
+        (ErrorKind::UndefinedVariable, 7),
+    ];
+    assert_eq!(errors(SEVERAL_ERRORS), expected);
+}
+
+#[test]
+fn errors_are_capped() {
+    let mut compiler = Compiler::new();
+    compiler.max_errors = 2;
+    assert_eq!(compiler.compile(SEVERAL_ERRORS).err().unwrap().len(), 2);
+    let (out, code) = c4(&["--max-errors=3"], SEVERAL_ERRORS);
+    assert_eq!((out.matches(": error: ").count(), code), (3, 255));
+    assert_eq!(c4(&["--max-errors=0"], SEVERAL_ERRORS), ("bad error limit: 0\n".to_string(), 255));
+}
+
+#[test]
+fn bad_declarations_resync_at_the_next_declaration() {
+    let source = "int a = 1 +\n\
+                  struct S { int x; };\n\
+                  int b = 1 +\n\
+                  union U { int x; char c; };\n\
+                  int c = 1 +\n\
+                  typedef int T;\n\
+                  int main() { struct S s; union U u; T t; s.x = 1; u.x = 2; t = 3; return 0; }\n";
+    assert_eq!(errors(source), [(ErrorKind::BadExpression, 2), (ErrorKind::BadExpression, 4), (ErrorKind::BadExpression, 6)]);
+}
+
+#[test]
+fn every_type_keyword_starts_a_declaration() {
+    let source = "int a = 1 +\n\
+                  void f(void) { }\n\
+                  int b = 1 +\n\
+                  unsigned long u;\n\
+                  int c = 1 +\n\
+                  double d;\n\
+                  int main() { f(); u = 1; d = 2.0; return 0; }\n";
+    assert_eq!(errors(source), [(ErrorKind::BadExpression, 2), (ErrorKind::BadExpression, 4), (ErrorKind::BadExpression, 6)]);
+}
+
+#[test]
+fn oversized_types_are_errors() {
+    let source = "int a[4611686018427387904];\n\
+                  struct S { char a[4611686018427387903]; char b[4611686018427387903]; };\n\
+                  int main() { return sizeof(int[2305843009213693952][4]); }\n";
+    assert_eq!(errors(source), [(ErrorKind::TypeTooLarge, 1), (ErrorKind::TypeTooLarge, 2), (ErrorKind::TypeTooLarge, 3)]);
+}
+
+#[test]
+fn globals_must_fit_in_the_data_segment() {
+    assert_eq!(errors("int big[100000];\nint main() { return 0; }\n"), [(ErrorKind::DataTooLarge, 1)]);
+    let strings: String = (0..3000).map(|i| format!("char *s{} = \"{:0>90}\";\n", i, i)).collect();
+    assert_eq!(errors(&format!("{}int main() {{ return 0; }}\n", strings))[0].0, ErrorKind::DataTooLarge);
+    // Jump tables live in the data segment too, and are made at the end of the switch
+    let cases: String = (0..100).map(|i| format!("case {}: return {};\n", i, i)).collect();
+    let source = format!("long big[32700];\nint main() {{ switch (big[0]) {{\n{}}} return 0; }}\n", cases);
+    assert_eq!(errors(&source), [(ErrorKind::DataTooLarge, 103)]);
+}
+
+#[test]
+fn locals_must_fit_on_the_stack() {
+    let source = "int f() { long x[40000]; x[0] = 1; return x[0]; }\nint main() { return f(); }\n";
+    assert_eq!(errors(source), [(ErrorKind::FrameTooLarge, 1)]);
+}
+
+#[test]
+fn empty_parameter_lists_leave_calls_unchecked() {
+    let source = "int f();\n\
+                  int g(int (*h)(), int x) { return h(x, 1); }\n\
+                  int add(int a, int b) { return a + b; }\n\
+                  int main() { return f(1) + g(add, 2); }\n\
+                  int f(int x) { return x; }\n";
+    assert_eq!(warnings(source), []);
+    // Only parameters that arguments without a prototype can be passed to agree with `()`
+    let source = "int c(char c);\nint c();\nint v();\nint v(int a, ...);\nint main() { return 0; }\n";
+    let kinds: Vec<_> = errors(source).into_iter().map(|(k, line)| (k.code(), line)).collect();
+    assert_eq!(kinds, [("conflicting-types", 2), ("conflicting-types", 4)]);
+}
+
+#[test]
+fn arguments_are_checked_against_pointer_parameters() {
+    let source = "int takes(int *p) { return *p; }\n\
+                  int any(void *p) { return 0; }\n\
+                  int main() { char s[4]; int *ip; takes(s); takes(ip); any(s); takes(0); return takes(ip); }\n";
+    assert_eq!(warnings(source), [(ErrorKind::IncompatiblePointer(1), 3)]);
+    let source = "int takes(int *p) { return *p; }\n\
+                  int fl(double d) { return d; }\n\
+                  int main() { double d; int *ip; takes(d); fl(ip); return 0; }\n";
+    assert_eq!(errors(source), [(ErrorKind::IncompatibleArgument(1), 3), (ErrorKind::IncompatibleArgument(1), 3)]);
+}
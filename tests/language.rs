@@ -0,0 +1,210 @@
+// Language features, checked through the exit codes and output of small programs
+
+mod common;
+
+use c4::error::{DeclKind, LvalueContext};
+use c4::ErrorKind;
+use common::{errors, exit_code, warnings};
+
+#[test]
+fn block_comments() {
+    let source = "/* licence\n   header */\n\
+                  int main() { /* one */ int x; x = 6 /* two */ * /**/ 7; /* a // b */ return x; }\n\
+                  /* a * in / the * middle **/\n";
+    assert_eq!(exit_code(source), 42);
+    // Lines keep counting through a comment, and one left open is an error
+    assert_eq!(errors("/*\n\n*/ int main() { return y; }\n"), [(ErrorKind::UndefinedVariable, 3)]);
+    assert_eq!(errors("int main() { return 0; }\n/* never\nclosed\n"), [(ErrorKind::UnterminatedComment, 2)]);
+}
+
+#[test]
+fn escape_sequences() {
+    let source = "int main() {\n\
+                  return '\\a' == 7 && '\\b' == 8 && '\\f' == 12 && '\\n' == 10 && '\\r' == 13 && '\\t' == 9\n\
+                  && '\\v' == 11 && '\\\\' == 92 && '\\'' == 39 && '\\\"' == 34 && '\\?' == 63 && '\\0' == 0\n\
+                  && '\\101' == 65 && '\\x41' == 65 && '\\x7f' == 127 && '\\377' == -1;\n\
+                  }\n";
+    assert_eq!(exit_code(source), 1);
+    // Strings take the same escapes, and "\0" ends them early
+    let source = "int main() { char *s; s = \"a\\tb\\x41\\101\\\"\\0z\"; return s[1] + s[3] + s[4] + s[5] + s[6]; }";
+    assert_eq!(exit_code(source), 9 + 65 + 65 + 34);
+    assert_eq!(errors("int main() { return '\\q'; }"), [(ErrorKind::BadEscape("\\q".to_string()), 1)]);
+}
+
+#[test]
+fn for_loops() {
+    assert_eq!(exit_code("int main() { int i; int s; s = 0; for (i = 1; i <= 10; i = i + 1) s = s + i; return s; }"), 55);
+    // The step runs after the body and before the next test
+    assert_eq!(exit_code("int main() { int i; int n; n = 0; for (i = 0; i < 3; i++) n = n * 10 + i + 1; return n + i * 1000; }"), 3123);
+    // Every clause may be left out
+    assert_eq!(exit_code("int main() { int i; i = 0; for (; i < 5;) i++; for (;;) { if (i == 9) return i; i++; } }"), 9);
+    assert_eq!(exit_code("int main() { int n; n = 0; for (n = 7; 0;) n = 1; return n; }"), 7);
+}
+
+#[test]
+fn do_while_break_and_continue() {
+    // The body of a do-while runs before the first test
+    assert_eq!(exit_code("int main() { int i; i = 10; do i++; while (i < 5); return i; }"), 11);
+    assert_eq!(exit_code("int main() { int i; i = 0; do { i = i + 2; } while (i < 9); return i; }"), 10);
+    // break leaves the innermost loop; continue goes to its test, or to the step of a for
+    let source = "int main() { int i; int j; int s; s = 0;\n\
+                  for (i = 0; i < 10; i++) { if (i == 2) continue; if (i == 6) break; s = s + i; }\n\
+                  i = 0; while (1) { i++; if (i % 2) continue; if (i > 8) break; s = s + 100; }\n\
+                  j = 0; do { j++; if (j < 3) continue; break; } while (1);\n\
+                  for (i = 0; i < 3; i++) for (;;) { s = s + 1000; break; }\n\
+                  return s + j * 10000; }";
+    assert_eq!(exit_code(source), 13 + 400 + 3000 + 30000);
+    assert_eq!(errors("int main() { break; }"), [(ErrorKind::BreakOutsideLoop, 1)]);
+    assert_eq!(errors("int main() {\nif (1) continue;\n}"), [(ErrorKind::ContinueOutsideLoop, 2)]);
+}
+
+#[test]
+fn switch_statements() {
+    // Falls through from case to case until a break; default catches the rest
+    let source = "int f(int x) { int r; r = 0; switch (x) { case 1: r = r + 1; case 2: r = r + 10; break; case 3: return 7; default: r = 100; } return r; }\n\
+                  int main() { return f(1) + f(2) * 1000 + f(3) * 100000 + f(9) * 1000000; }";
+    assert_eq!(exit_code(source), 11 + 10_000 + 700_000 + 100_000_000);
+    // Dense cases use a jump table, sparse ones a chain of tests; both find every case
+    let source = "int dense(int x) { switch (x) { case 3: return 30; case 4: return 40; case 5: return 50; case 6: return 60; case 7: return 70; case 8: return 80; } return -1; }\n\
+                  int sparse(long x) { switch (x) { case -1000000: return 1; case 7: return 2; case 4000000000: return 3; } return 0; }\n\
+                  int main() { int i; int s; s = 0; for (i = 0; i < 12; i++) s = s + dense(i); return s + sparse(-1000000) * 1000 + sparse(7) * 10000 + sparse(4000000000) * 100000 + sparse(8); }";
+    assert_eq!(exit_code(source), 330 - 6 + 1000 + 20000 + 300000);
+    // A switch with no matching case and no default does nothing
+    assert_eq!(exit_code("int main() { int r; r = 5; switch (r) { case 1: r = 0; } switch (r) { } return r; }"), 5);
+}
+
+#[test]
+fn case_values_convert_to_the_controlling_type() {
+    let source = "int main() { unsigned u; unsigned char uc; char c; int hits; hits = 0;\n\
+                  u = 0xFFFFFFFF; switch (u) { case -1: hits = hits + 1; break; default: hits = hits + 100; }\n\
+                  uc = 255; switch (uc) { case -1: hits = hits + 100; break; case 255: hits = hits + 2; }\n\
+                  c = -1; switch (c) { case 255: hits = hits + 100; break; case -1: hits = hits + 4; }\n\
+                  return hits; }";
+    assert_eq!(exit_code(source), 7);
+    let source = "int f(unsigned u) {\nswitch (u) { case -1: return 0;\ncase 4294967295: return 1; }\nreturn 2; }\nint main() { return f(1); }";
+    assert_eq!(errors(source), [(ErrorKind::DuplicateCase, 3)]);
+    assert_eq!(errors("int main() { double d; d = 1;\nswitch (d) { default: return 1; } }"), [(ErrorKind::SwitchNotInteger, 2)]);
+    assert_eq!(errors("int main() { char *p; p = 0;\nswitch (p) { default: return 1; } }"), [(ErrorKind::SwitchNotInteger, 2)]);
+}
+
+#[test]
+fn goto_and_labels() {
+    // Jumps go forwards and backwards, and into and out of blocks
+    let source = "int main() { int i; int s; i = 0; s = 0;\n\
+                  again: i++; if (i < 5) goto again;\n\
+                  goto skip; s = 100;\n\
+                  skip: while (1) { s = s + i; if (s > 20) goto out; }\n\
+                  out: return s; }";
+    assert_eq!(exit_code(source), 25);
+    // Labels belong to the function, not the block, and have a namespace of their own
+    assert_eq!(exit_code("int x; int main() { x = 1; { x: x = x + 1; } if (x < 3) goto x; return x; }"), 3);
+    let source = "int main() {\ngoto nowhere;\nl: return 0;\nl: return 1;\n}";
+    assert_eq!(errors(source), [(ErrorKind::DuplicateLabel, 4), (ErrorKind::UndefinedLabel, 2)]);
+    assert_eq!(errors("int main() { goto 3; }"), [(ErrorKind::BadLabel, 1)]);
+    assert_eq!(warnings("int main() {\nunused: return 0; }"), [(ErrorKind::UnusedLabel, 2)]);
+}
+
+#[test]
+fn initializers() {
+    // Members left out are zero, braces may be elided, and a string sizes its array
+    let source = "int m[2][2] = { { 1, 2 }, 3 };\n\
+                  struct s { int a; char *s; int v[3]; } sv = { 1, \"hi\", { 2, 3 } };\n\
+                  char str[] = \"abc\";\n\
+                  int main() { return m[0][1] + m[1][0] + m[1][1] + sv.a + sv.s[1] + sv.v[1] + sv.v[2] + sizeof(str) + str[2]; }";
+    assert_eq!(exit_code(source), 2 + 3 + 1 + 105 + 3 + 4 + 99);
+    let source = "int main() { int a[3] = { 1, 2 }; struct { char c; int n; } s = { 'a', 5 }; char t[] = \"hey\";\n\
+                  int k = a[0] + a[1]; return k + a[2] + s.c + s.n + sizeof(t) + t[1]; }";
+    assert_eq!(exit_code(source), 3 + 97 + 5 + 4 + 101);
+    // A global may hold the address of a function defined further on
+    let source = "int g(int);\nint (*gp)(int) = g;\nint (*tab[2])(int) = { 0, g };\n\
+                  int main() { return gp(2) + tab[1](3); }\nint g(int x) { return x * 10; }";
+    assert_eq!(exit_code(source), 50);
+    assert_eq!(errors("int g(int);\nint (*gp)(int) = g;\nint main() { return 0; }"), [(ErrorKind::UndefinedFunction("g".to_string()), 2)]);
+    assert_eq!(errors("int g(int);\nint x = g + 1;\nint main() { return 0; }"), [(ErrorKind::NotConstant, 2)]);
+    assert_eq!(errors("int n;\nint x = n;\nint main() { return 0; }"), [(ErrorKind::NotConstant, 2)]);
+    assert_eq!(errors("int a[2] = { 1, 2,\n3 };\nint main() { return 0; }"), [(ErrorKind::ExcessInitializers, 2)]);
+}
+
+#[test]
+fn block_scopes() {
+    // Declarations may follow statements, and each block hides and then restores names
+    let source = "int x = 1;\n\
+                  int main() { int r = x; int x = 10; r = r * 100 + x;\n\
+                  { char x = 3; r = r + x; { int x = 4; r = r + x; } r = r + x; }\n\
+                  r = r + x; for (int i = 0; i < 2; i++) { int x = i; r = r + x; } return r; }";
+    assert_eq!(exit_code(source), 131);
+    // Tags are scoped too
+    let source = "struct t { int a; };\n\
+                  int main() { struct t o; o.a = 1; { struct t { char c; int d; } i; i.d = 5; o.a = o.a + i.d + sizeof(i); }\n\
+                  { struct t n; n.a = 2; return o.a + n.a + sizeof(struct t); } }";
+    assert_eq!(exit_code(source), 20);
+    // Sibling blocks share their stack slots
+    assert_eq!(exit_code("int main() { int *p; int *q; { int a; p = &a; } { int b; q = &b; } return p == q; }"), 1);
+    assert_eq!(errors("int main() {\n{ int a = 1; }\nreturn a; }"), [(ErrorKind::UndefinedVariable, 3)]);
+    assert_eq!(errors("int main() {\nint a = 1;\nint a = 2;\nreturn a; }"), [(ErrorKind::DuplicateDefinition(DeclKind::Local), 3)]);
+}
+
+#[test]
+fn compound_assignment_and_comma() {
+    let source = "int main() { int a = 5; a += 3; a -= 1; a *= 6; a /= 4; a %= 7; return a; }";
+    assert_eq!(exit_code(source), 3);
+    let source = "int main() { int a = 1; a <<= 4; a >>= 1; a |= 3; a &= 10; a ^= 15; return a; }";
+    assert_eq!(exit_code(source), 5);
+    // Pointers step by whole elements, and the value is converted back to the target's type
+    let source = "int main() { int v[4] = { 1, 2, 3, 4 }; int *p = v; int r; p += 3; r = *p; p -= 2; return r * 10 + *p; }";
+    assert_eq!(exit_code(source), 42);
+    assert_eq!(exit_code("int main() { char c = 250; c += 10; double d = 2.5; int i = 3; i += d; d *= 2; return c * 100 + i * 10 + d; }"), 455);
+    // The target's address is worked out once
+    assert_eq!(exit_code("int n; int *next() { n++; return &n; } int main() { *next() += 5; *next() *= 2; return n; }"), 14);
+    // Comma evaluates left to right and gives its right operand
+    assert_eq!(exit_code("int main() { int a; int x = (a = 2, a + 1); return x * 10 + a; }"), 32);
+    assert_eq!(exit_code("int main() { int a; int x; for (a = 0, x = 0; a < 3; a++, x += 2) ; return x; }"), 6);
+    assert_eq!(errors("int main() {\n3 += 1; return 0; }"), [(ErrorKind::BadLvalue(LvalueContext::Assignment), 2)]);
+}
+
+#[test]
+fn forward_declarations() {
+    // Calls made before the definition are patched when it is seen
+    let source = "int odd(int n);\nint later(int);\n\
+                  int even(int n) { if (n == 0) return 1; return odd(n - 1); }\n\
+                  int odd(int n) { if (n == 0) return 0; return even(n - 1); }\n\
+                  int main() { return even(10) * 10 + later(3); }\n\
+                  int later(int x) { return x * 2; }";
+    assert_eq!(exit_code(source), 16);
+    let source = "int f(int);\nint main() {\nreturn f(1) + f(2);\n}\nint g() {\nreturn f(3);\n}";
+    assert_eq!(errors(source), [(ErrorKind::UndefinedFunction("f".to_string()), 3)]);
+    assert_eq!(errors("int main() {\nreturn nowhere(1); }"), [(ErrorKind::UndeclaredFunction("nowhere".to_string()), 2)]);
+    // The operand of sizeof is not evaluated, so what it calls need not be defined
+    assert_eq!(exit_code("int f(); int main() { int x; x = sizeof(f()); return x; } int f() { return 1; }"), 4);
+    assert_eq!(exit_code("char f();\nint main() { return sizeof f() + sizeof(f(), 1); }"), 5);
+}
+
+#[test]
+fn typedefs() {
+    // Typedef names work in declarations, casts and sizeof, and an inner scope may
+    // redefine one or reuse the name for a variable
+    let source = "typedef int myint;\ntypedef char *str;\n\
+                  typedef struct point { int x; int y; } point, *ppoint;\n\
+                  typedef int (*binop)(int, int);\ntypedef int arr3[3];\n\
+                  int add(int a, int b) { return a + b; }\n\
+                  int main() { myint i = 2; str s = \"hey\"; point p; ppoint pp = &p; pp->x = 3; p.y = 4;\n\
+                  binop f = add; arr3 a = { 1, 2, 3 };\n\
+                  myint r = (myint)'A' + sizeof(myint) + sizeof(point) + sizeof(arr3) + (int)sizeof(str);\n\
+                  { typedef char myint; myint c = 300; r = r + c + sizeof(myint); }\n\
+                  int myint = 7; return r - 200 + i + s[1] + f(p.x, pp->y) + a[2] + myint; }";
+    assert_eq!(exit_code(source), 62);
+    assert_eq!(errors("typedef int t;\ntypedef char t;\nint main() { return 0; }"), [(ErrorKind::DuplicateDefinition(DeclKind::Typedef), 2)]);
+    assert_eq!(errors("typedef int t;\nint main() { t x; x = 1;\nreturn t; }"), [(ErrorKind::BadExpression, 3)]);
+}
+
+#[test]
+fn only_lvalues_are_assigned() {
+    // A constant whose value happens to equal a load opcode is still not an lvalue
+    assert_eq!(errors("int main() {\n42 = 2; return 0; }"), [(ErrorKind::BadLvalue(LvalueContext::Assignment), 2)]);
+    assert_eq!(errors("int main() { int *p;\np = &41; return 0; }"), [(ErrorKind::BadAddressOf, 2)]);
+    assert_eq!(errors("int main() {\n41++; return 0; }"), [(ErrorKind::BadLvalue(LvalueContext::PostIncrement), 2)]);
+    assert_eq!(errors("int main() { int x; x = 1;\n++x = 3; return x; }"), [(ErrorKind::BadLvalue(LvalueContext::Assignment), 2)]);
+    // Loads of every width still give lvalues
+    let source = "int main() { short s = 1; unsigned char c = 255; long l = 2; s += 2; c++; ++l; long *p = &l; return s * 100 + c * 10 + *p; }";
+    assert_eq!(exit_code(source), 303);
+}
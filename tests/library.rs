@@ -0,0 +1,87 @@
+// Using the compiler and virtual machine as a library: reuse and threads
+
+mod common;
+
+use c4::{Compiler, ErrorKind, Vm};
+use common::run;
+
+#[test]
+fn compiler_can_be_reused() {
+    let mut compiler = Compiler::new();
+    assert_eq!(run(&mut compiler, "int g; int main() { g = 3; return g * 2; }"), 6);
+    // The same names again: nothing is left over from the first program
+    assert_eq!(run(&mut compiler, "int g; int f(int x) { return x + 1; } int main() { g = 4; return f(g); }"), 5);
+    assert_eq!(run(&mut compiler, "char *s = \"abc\"; int main() { return s[2]; }"), 'c' as i64);
+}
+
+#[test]
+fn reuse_after_errors() {
+    let mut compiler = Compiler::new();
+    let errors = compiler.compile("int main() { return x; }").err().unwrap();
+    assert!(matches!(errors[0].kind, ErrorKind::UndefinedVariable));
+    assert_eq!(run(&mut compiler, "int x; int main() { x = 7; return x; }"), 7);
+}
+
+#[test]
+fn macros_do_not_leak_between_programs() {
+    let mut compiler = Compiler::new();
+    compiler.define("BASE", "10");
+    assert_eq!(run(&mut compiler, "#define EXTRA 1\nint main() { return BASE + EXTRA; }"), 11);
+    let errors = compiler.compile("int main() { return BASE + EXTRA; }").err().unwrap();
+    assert!(matches!(errors[0].kind, ErrorKind::UndefinedVariable));
+}
+
+#[test]
+fn compilers_run_on_threads() {
+    let handles: Vec<_> = (0..8)
+        .map(|n| {
+            std::thread::spawn(move || {
+                let source = format!(
+                    "int fib(int n) {{ if (n < 2) return n; return fib(n - 1) + fib(n - 2); }}\n\
+                     int main() {{ return fib({}); }}",
+                    n + 5
+                );
+                run(&mut Compiler::new(), &source)
+            })
+        })
+        .collect();
+    let results: Vec<i64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert_eq!(results, [5, 8, 13, 21, 34, 55, 89, 144]);
+}
+
+#[test]
+fn programs_move_between_threads() {
+    let program = Compiler::new().compile("int main() { return 42; }").ok().unwrap();
+    let code = std::thread::spawn(move || Vm::new(program).run(&["prog".to_string()])).join().unwrap();
+    assert_eq!(code, 42);
+}
+
+#[test]
+fn oversized_program_is_an_error() {
+    let body: String = (0..9000).map(|i| format!("x = x + {};\n", i)).collect();
+    let source = format!("int main() {{ int x; x = 0;\n{}return x; }}", body);
+    let errors = Compiler::new().compile(&source).err().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0].kind, ErrorKind::ProgramTooLarge));
+}
+
+#[test]
+fn stack_overflow_stops_the_program() {
+    let mut compiler = Compiler::new();
+    assert_eq!(run(&mut compiler, "int f(int n) { return n == 0 ? 0 : 1 + f(n - 1); } int main() { return f(100000); }"), -1);
+    let source = "int f() { long x[30000]; x[0] = 1; return x[0]; } int main() { long y[10000]; y[0] = 2; return f() + y[0]; }";
+    assert_eq!(run(&mut compiler, source), -1);
+    assert_eq!(run(&mut compiler, "int f(int n) { return n == 0 ? 0 : 1 + f(n - 1); } int main() { return f(1000); }"), 1000);
+}
+
+#[test]
+fn division_by_zero_stops_the_program() {
+    let mut compiler = Compiler::new();
+    for op in ["/", "%"] {
+        for ty in ["int", "unsigned", "unsigned char", "long"] {
+            let source = format!("{} zero; int main() {{ {} x; x = 7; return x {} zero; }}", ty, ty, op);
+            assert_eq!(run(&mut compiler, &source), -1, "{} {}", ty, op);
+        }
+    }
+    assert_eq!(run(&mut compiler, "int d; int main() { d = 2; return 7 / d + 7 % d; }"), 4);
+}
@@ -0,0 +1,126 @@
+// Whole programs compiled and run by the c4 binary, checked by what they print
+
+mod common;
+
+use common::output;
+
+#[test]
+fn functions_returning_function_pointers() {
+    let source = "#include <stdio.h>\n\
+                  int inc(int x) { return x + 1; }\n\
+                  int (*pick(void))(int) { return inc; }\n\
+                  int (*twice(int))(int);\n\
+                  int main() {\n\
+                      int (*h)(int);\n\
+                      h = twice(3);\n\
+                      printf(\"%d %d %d\\n\", pick()(41), h(9), twice(0)(pick()(0)));\n\
+                      return 0;\n\
+                  }\n\
+                  int (*twice(int n))(int) { return n ? inc : pick(); }\n";
+    assert_eq!(output(source), ("42 10 2\n".to_string(), 0));
+}
+
+#[test]
+fn floating_point_conversions() {
+    let source = "#include <stdio.h>\n\
+                  int main() {\n\
+                      double d; float f; int i; long l; unsigned u;\n\
+                      d = 7.9; i = d; f = -2.5; l = f;\n\
+                      printf(\"%d %ld %d\\n\", i, l, (int)-7.9);\n\
+                      i = 3; d = i / 2; printf(\"%f %f\\n\", d, i / 2.0);\n\
+                      u = 4000000000u; d = u; printf(\"%.1f\\n\", d);\n\
+                      d = 3e9; u = d; printf(\"%u\\n\", u);\n\
+                      f = 0.1f; d = 0.1; printf(\"%d %d\\n\", f == d, (double)f == (float)d);\n\
+                      return 0;\n\
+                  }\n";
+    let expected = "7 -2 -7\n1.000000 1.500000\n4000000000.0\n3000000000\n0 1\n";
+    assert_eq!(output(source), (expected.to_string(), 0));
+}
+
+#[test]
+fn floating_point_comparisons() {
+    let source = "#include <stdio.h>\n\
+                  int main() {\n\
+                      double x; x = 0.5;\n\
+                      printf(\"%d %d %d %d\\n\", 1.5 < 2, 2.0 >= 2, -0.0 == 0.0, 3.0f != 3);\n\
+                      printf(\"%d %d %d\\n\", x > 0.25 && x <= 0.5, !x, x ? 1 : 2);\n\
+                      while (x < 4) x = x * 2;\n\
+                      printf(\"%g\\n\", x);\n\
+                      return 0;\n\
+                  }\n";
+    assert_eq!(output(source), ("1 1 1 0\n1 0 1\n4\n".to_string(), 0));
+}
+
+#[test]
+fn printf_floating_point_formats() {
+    let source = "#include <stdio.h>\n\
+                  int main() {\n\
+                      float f; f = 0.25;\n\
+                      printf(\"%f %g %e\\n\", 3.14159, 0.0001, 12345.678);\n\
+                      printf(\"%.2f %10.3f|%-8g|%g %g\\n\", 2.0 / 3, -1.5, 2.5, 1e20, 100.0);\n\
+                      printf(\"%f %d %s\\n\", f, 5, \"end\");\n\
+                      return 0;\n\
+                  }\n";
+    let expected = "3.141590 0.0001 1.234568e+04\n0.67     -1.500|2.5     |1e+20 100\n0.250000 5 end\n";
+    assert_eq!(output(source), (expected.to_string(), 0));
+}
+
+#[test]
+fn unsigned_division_and_shifts() {
+    let source = "#include <stdio.h>\n\
+                  int main() {\n\
+                      unsigned u; unsigned long ul; int i; char c; unsigned char uc;\n\
+                      u = 7; printf(\"%d %u\\n\", -1 < u, -1 / u);\n\
+                      u = 0xF0000000; printf(\"%u %u %u\\n\", u >> 4, u / 3, u % 7);\n\
+                      ul = 18446744073709551615ul; printf(\"%lu %lu %lu\\n\", ul / 10, ul % 10, ul >> 60);\n\
+                      i = -16; printf(\"%d %d\\n\", i >> 2, i / 3);\n\
+                      c = -1; uc = c; printf(\"%d %d %u\\n\", c, uc, (unsigned)c);\n\
+                      uc = 200; printf(\"%d\\n\", uc / 3 + uc % 7);\n\
+                      return 0;\n\
+                  }\n";
+    let expected = "0 613566756\n\
+                    251658240 1342177280 2\n\
+                    1844674407370955161 5 15\n\
+                    -4 -5\n\
+                    -1 255 4294967295\n\
+                    70\n";
+    assert_eq!(output(source), (expected.to_string(), 0));
+}
+
+#[test]
+fn builtin_headers() {
+    let source = "#include <stdio.h>\n#include <stdlib.h>\n#include <string.h>\n#include <fcntl.h>\n#include <unistd.h>\n\
+                  int main() {\n\
+                      char *p; p = malloc(4); memset(p, 'x', 3); p[3] = 0;\n\
+                      printf(\"%s %d %d\\n\", p, memcmp(p, \"xxx\", 4), NULL == 0);\n\
+                      free(p);\n\
+                      return open(\"/nonexistent/file\", O_RDONLY) < 0;\n\
+                  }\n";
+    assert_eq!(output(source), ("xxx 0 1\n".to_string(), 1));
+}
+
+#[test]
+fn control_flow_and_escapes_together() {
+    let source = "#include <stdio.h>\n\
+                  int fib(int n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }\n\
+                  int main() {\n\
+                      int i; int s;\n\
+                      s = 0;\n\
+                      for (i = 0; i < 10; i++) { if (i == 7) break; if (i == 2) continue; s += i; }\n\
+                      printf(\"s=%d fib=%d\\n\", s, fib(15));\n\
+                      i = 0;\n\
+                      do { i++; } while (i < 5);\n\
+                      printf(\"i=%d\\n\", i);\n\
+                      switch (i) { case 1: printf(\"one\\n\"); case 5: printf(\"five\\n\"); case 6: printf(\"six\\n\"); break; default: printf(\"def\\n\"); }\n\
+                      switch (100) { case 1: printf(\"x\\n\"); break; case 1000: printf(\"y\\n\"); break; default: printf(\"def2\\n\"); }\n\
+                      goto L;\n\
+                      printf(\"skipped\\n\");\n\
+                  L:\n\
+                      printf(\"%d %d %d %d %d\\n\", '\\t', '\\x41', '\\101', '\\0', '\\\\');\n\
+                      /* block\n\
+                         comment */\n\
+                      return 3;\n\
+                  }\n";
+    let expected = "s=19 fib=610\ni=5\nfive\nsix\ndef2\n9 65 65 0 92\n";
+    assert_eq!(output(source), (expected.to_string(), 3));
+}